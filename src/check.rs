@@ -0,0 +1,103 @@
+use crate::*;
+
+// An independent checker for equational proofs.
+// It only relies on matching and substituting terms, not on the completion procedure,
+// so a proof accepted here holds even if completion itself is buggy.
+
+// checks that the proof derives the goal from the axioms, where the ids in the steps index into axioms.
+pub fn check_proof(axioms: &[Equation], goal: &Equation, proof: &Proof) -> Result<(), String> {
+    if proof.lhs != goal.lhs {
+        return Err(format!("proof starts with {}, not with {}", proof.lhs, goal.lhs));
+    }
+
+    let mut term = proof.lhs.clone();
+    for (i, step) in proof.steps.iter().enumerate() {
+        let next = check_step(axioms, &term, step).map_err(|e| format!("step {}: {e}", i+1))?;
+        if next != step.term {
+            return Err(format!("step {}: rewriting yields {next}, not {}", i+1, step.term));
+        }
+        term = next;
+    }
+
+    if term != goal.rhs {
+        return Err(format!("proof ends with {term}, not with {}", goal.rhs));
+    }
+    Ok(())
+}
+
+// applies the axiom of the step to term, and returns the result.
+fn check_step(axioms: &[Equation], term: &Term, step: &Rewrite) -> Result<Term, String> {
+    let Some(axiom) = axioms.get(step.rule) else { return Err(format!("unknown axiom {}", step.rule)) };
+    let (l, r) = if step.reversed { (&axiom.rhs, &axiom.lhs) } else { (&axiom.lhs, &axiom.rhs) };
+    let Some(redex) = subterm(term, &step.pos) else { return Err(format!("invalid position {:?}", step.pos)) };
+
+    // rename the axiom apart from the term, as required for matching.
+    let taken = get_vars(term);
+    let mut renaming = Subst::new();
+    let mut fresh = (0..).map(|i| gsymb_add(format!("C{i}"))).filter(|x| !taken.contains_key(x));
+    for x in get_vars(l).into_keys().chain(get_vars(r).into_keys()) {
+        renaming.entry(x).or_insert_with(|| Term::Var(fresh.next().unwrap()));
+    }
+    let (l2, r2) = (apply_subst(l, &renaming), apply_subst(r, &renaming));
+
+    let Some(mut subst) = pat_match(&l2, redex) else {
+        return Err(format!("axiom {} {axiom} does not match {redex}", step.rule));
+    };
+    // variables only occurring on the other side are instantiated as given by the step.
+    for (x, y) in &renaming {
+        let Term::Var(y) = y else { unreachable!() };
+        if subst.contains_key(y) { continue }
+        let Some(t) = step.subst.get(x) else { return Err(format!("no instance given for {x}")) };
+        subst.insert(*y, t.clone());
+    }
+
+    Ok(pos_set(term, &step.pos, &apply_subst(&r2, &subst)))
+}
+
+// t[pos], if pos is a position of t.
+fn subterm<'t>(t: &'t Term, pos: &[usize]) -> Option<&'t Term> {
+    let Some((i, rest)) = pos.split_first() else { return Some(t) };
+    let Term::Fun(_, args) = t else { return None };
+    subterm(args.get(*i)?, rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    fn axioms() -> Vec<Equation> {
+        ["f(f(X)) = g(X)", "f(a) = b"].iter().map(|e| Equation::parse(e).unwrap()).collect()
+    }
+
+    fn step(rule: Id, reversed: bool, pos: Pos, subst: &[(&str, &str)], term: &str) -> Rewrite {
+        let subst = subst.iter().map(|(x, t)| (gsymb_add(x.to_string()), Term::parse(t).unwrap())).collect();
+        Rewrite { rule, reversed, pos, subst, term: Term::parse(term).unwrap() }
+    }
+
+    #[test]
+    fn check_valid() {
+        let goal = Equation::parse("f(f(f(a))) = g(b)").unwrap();
+        let proof = Proof {
+            lhs: goal.lhs.clone(),
+            steps: vec![
+                step(0, false, vec![], &[("X", "f(a)")], "g(f(a))"),
+                step(1, false, vec![0], &[], "g(b)"),
+            ],
+        };
+        assert_eq!(check_proof(&axioms(), &goal, &proof), Ok(()));
+
+        // a step in the wrong direction.
+        let mut bad = proof.clone();
+        bad.steps[1].reversed = true;
+        assert!(check_proof(&axioms(), &goal, &bad).is_err());
+
+        // a wrong position.
+        let mut bad = proof.clone();
+        bad.steps[1].pos = vec![0, 0];
+        assert!(check_proof(&axioms(), &goal, &bad).is_err());
+
+        // a different goal.
+        let goal = Equation::parse("f(f(f(a))) = g(a)").unwrap();
+        assert!(check_proof(&axioms(), &goal, &proof).is_err());
+    }
+}
@@ -0,0 +1,81 @@
+use crate::*;
+
+// The result of deciding "lhs = rhs" on a (completed) state.
+pub struct Decision {
+    pub equal: bool,
+
+    // normal forms of both sides.
+    pub lhs: Term,
+    pub rhs: Term,
+
+    // the rewrite steps leading from the original sides to their normal forms.
+    pub lhs_steps: Vec<Rewrite>,
+    pub rhs_steps: Vec<Rewrite>,
+}
+
+// decides the word problem s = t by comparing normal forms.
+// This is only complete, if the state is convergent, i.e. if kbc saturated.
+pub fn decide(s: &Term, t: &Term, state: &State) -> Decision {
+    // rename the variables apart from the rules; and rename them back in the end.
+    let (eq, rev) = canonize_vars_d_rev(Equation::new(s.clone(), t.clone()));
+    let Equation { lhs: s, rhs: t, .. } = eq;
+
+    let (lhs, lhs_steps) = normalize(s, state);
+    let (rhs, rhs_steps) = normalize(t, state);
+
+    let unrename = |steps: Vec<Rewrite>| {
+        steps.into_iter()
+             .map(|step| Rewrite {
+                 subst: step.subst.iter().map(|(x, t)| (*x, apply_subst(t, &rev))).collect(),
+                 term: apply_subst(&step.term, &rev),
+                 ..step
+             })
+             .collect()
+    };
+
+    Decision {
+        equal: lhs == rhs,
+        lhs: apply_subst(&lhs, &rev),
+        rhs: apply_subst(&rhs, &rev),
+        lhs_steps: unrename(lhs_steps),
+        rhs_steps: unrename(rhs_steps),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    fn rules(rs: &[&str]) -> State {
+        let mut state = State::new();
+        for r in rs {
+            state.add_rule(Equation::parse(r).unwrap().into_rule());
+        }
+        state
+    }
+
+    #[test]
+    fn decide_ground() {
+        let state = rules(&["f(f(X)) = g(X)", "f(a) = b"]);
+        let s = Term::parse("f(f(f(a)))").unwrap();
+        let t = Term::parse("g(b)").unwrap();
+        let d = decide(&s, &t, &state);
+        assert!(d.equal);
+        assert_eq!(d.lhs, t);
+        assert_eq!(d.lhs_steps.len(), 2);
+        assert_eq!(d.lhs_steps[0].rule, 0);
+        assert_eq!(d.lhs_steps[0].pos, Vec::<usize>::new());
+        assert!(d.rhs_steps.is_empty());
+    }
+
+    #[test]
+    fn decide_vars() {
+        let state = rules(&["f(f(X)) = g(X)"]);
+        let s = Term::parse("f(f(X))").unwrap();
+        let t = Term::parse("g(Y)").unwrap();
+        let d = decide(&s, &t, &state);
+        assert!(!d.equal);
+        assert_eq!(d.lhs, Term::parse("g(X)").unwrap());
+        assert_eq!(d.rhs, t);
+    }
+}
@@ -1,27 +1,74 @@
 use crate::*;
 
-pub fn deduce_step(mut state: State) -> State {
-    let mut cps = Vec::new();
-    for l@(_, _, lori) in &state {
-        if !lori { continue }
-        for r@(_, _, rori) in &state {
-            if !rori { continue }
-            let (la, lb, _) = canonize_vars_l(l.clone());
-            let (ra, rb, _) = canonize_vars_r(r.clone());
+// all critical pairs between the active fact with the given id and the active facts (including itself).
+// In unfailing mode, the equations are used in both directions as well,
+// and only overlaps whose instances are not ordered against the direction of use are considered.
+// With sorts, only well-sorted overlaps are considered.
+// The critical pairs are neither simplified nor deduplicated yet.
+pub fn deduce(id: Id, state: &State, unfailing: bool) -> Vec<Equation> {
+    let facts: Vec<Directed> = directed(state).into_iter()
+        .filter(|d| unfailing || d.oriented)
+        .collect();
+    let given: Vec<&Directed> = facts.iter().filter(|d| d.id == id).collect();
 
-            for p in positions(&ra) {
-                let Some(sig) = unify(&la, pos_idx(&ra, &p)) else { continue };
-                let ll = apply_subst(&pos_set(&ra, &p, &lb), &sig);
-                let rr = apply_subst(&rb, &sig);
-                let eq = (ll, rr, false);
-                let eq = simplify_converge(eq, &state);
-                let eq = canonize_vars(eq);
-                if eq.0 != eq.1 && !cps.contains(&eq) && !state.contains(&eq) {
-                    cps.push(eq);
-                }
-            }
+    // the given fact inside the others, and the others (but not itself again) inside the given fact.
+    let mut cps: Vec<Equation> = Vec::new();
+    for l in &given {
+        for r in &facts {
+            overlaps(l, r, state, &mut cps);
         }
     }
-    state.extend(cps);
-    state
+    for l in facts.iter().filter(|d| d.id != id) {
+        for r in &given {
+            overlaps(l, r, state, &mut cps);
+        }
+    }
+    cps
+}
+
+// the critical pairs from overlapping l into the non-variable positions of r.
+fn overlaps(l: &Directed, r: &Directed, state: &State, cps: &mut Vec<Equation>) {
+    let order = &*state.order;
+    let sig = &state.signature;
+
+    // rename both facts apart.
+    let l_ren = renaming(&Equation::new(l.lhs.clone(), l.rhs.clone()), l_name);
+    let r_ren = renaming(&Equation::new(r.lhs.clone(), r.rhs.clone()), r_name);
+    let (la, lb) = (apply_subst(l.lhs, &l_ren), apply_subst(l.rhs, &l_ren));
+    let (ra, rb) = (apply_subst(r.lhs, &r_ren), apply_subst(r.rhs, &r_ren));
+    let mut vars = rename_sorts(l.sorts, &l_ren);
+    vars.extend(rename_sorts(r.sorts, &r_ren));
+    if sig.is_sorted() {
+        vars.extend(sig.var_sorts(&la, &lb));
+        vars.extend(sig.var_sorts(&ra, &rb));
+    }
+
+    for p in positions(&ra) {
+        // overlaps at variables are not needed: by the critical pair lemma for ordered rewriting,
+        // they are joinable on ground terms, which suffices for ground confluence.
+        if let Term::Var(_) = pos_idx(&ra, &p) { continue }
+        let mut cp_vars = vars.clone();
+        let unifier = if sig.is_sorted() { unify_sorted(&la, pos_idx(&ra, &p), sig, &mut cp_vars) } else { unify(&la, pos_idx(&ra, &p)) };
+        let Some(sig) = unifier else { continue };
+        if order.gt(&apply_subst(&lb, &sig), &apply_subst(&la, &sig)) { continue }
+        if order.gt(&apply_subst(&rb, &sig), &apply_subst(&ra, &sig)) { continue }
+
+        let ll = apply_subst(&pos_set(&ra, &p, &lb), &sig);
+        let rr = apply_subst(&rb, &sig);
+        let mut eq = Equation::new(ll, rr);
+        // the variables of the critical pair are the unbound ones of the overlap.
+        eq.meta.sorts = cp_vars.into_iter().filter(|(x, _)| !sig.contains_key(x)).collect();
+
+        let instantiate = |ren: &Subst| ren.iter().map(|(x, t)| (*x, apply_subst(t, &sig))).collect();
+        eq.meta.origin = Origin::CriticalPair {
+            inner: l.id,
+            inner_reversed: l.reversed,
+            inner_subst: instantiate(&l_ren),
+            outer: r.id,
+            outer_reversed: r.reversed,
+            outer_subst: instantiate(&r_ren),
+            pos: p,
+        };
+        cps.push(canonize_vars(eq));
+    }
 }
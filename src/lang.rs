@@ -30,25 +30,181 @@ impl Term {
         Term::Fun(x, Box::new([]))
     }
 
+    // the term as printed by Display, i.e. in prefix notation with quoted symbols.
+    #[allow(clippy::inherent_to_string_shadow_display)]
     pub fn to_string(&self) -> String {
+        format!("{self}")
+    }
+}
+
+pub type Id = usize;
+
+// where an equation or rule came from.
+// Every fact of a State is recorded in its history under its id, so following the parents
+// yields the derivation DAG of any fact down to the input.
+// Substitutions and terms are expressed in the variables of the derived fact.
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub enum Origin {
+    // given in the input.
+    #[default]
+    Input,
+
+    // the critical pair of the overlap of the fact `inner` into the lhs of the fact `outer` at position pos
+    // (each used from right to left, if reversed).
+    // With l -> r = inner and s -> t = outer, the substitutions instantiate them such that
+    // s'|pos = l', and the critical pair is s'[r']pos = t'.
+    CriticalPair {
+        inner: Id,
+        inner_reversed: bool,
+        inner_subst: Subst,
+        outer: Id,
+        outer_reversed: bool,
+        outer_subst: Subst,
+        pos: Pos,
+    },
+
+    // the fact `from` with its variables renamed, and then its sides rewritten by the steps.
+    // The result may have its sides swapped, e.g. when it was oriented from right to left.
+    Simplified {
+        from: Id,
+        renaming: Subst,
+        lhs_steps: Vec<Rewrite>,
+        rhs_steps: Vec<Rewrite>,
+    },
+}
+
+impl Origin {
+    // applies the variable renaming to the recorded substitutions and terms.
+    pub fn rename(&mut self, renaming: &Subst) {
+        let rename_subst = |s: &mut Subst| {
+            for t in s.values_mut() {
+                *t = apply_subst(t, renaming);
+            }
+        };
         match self {
-            Term::Var(v) => gsymb_get(*v),
-            Term::Fun(f, args) => {
-                let f = gsymb_get(*f);
-                if args.len() == 0 { return f }
-                let mut s = f;
-                s.push('(');
-                for (i, a) in args.iter().enumerate() {
-                    s.push_str(&a.to_string());
-                    if i != args.len() - 1 {
-                        s.push_str(", ");
-                    }
+            Origin::Input => {},
+            Origin::CriticalPair { inner_subst, outer_subst, .. } => {
+                rename_subst(inner_subst);
+                rename_subst(outer_subst);
+            },
+            Origin::Simplified { renaming: r, lhs_steps, rhs_steps, .. } => {
+                rename_subst(r);
+                for step in lhs_steps.iter_mut().chain(rhs_steps) {
+                    rename_subst(&mut step.subst);
+                    step.term = apply_subst(&step.term, renaming);
                 }
-                s.push(')');
-                s
             },
         }
     }
+
+    // the ids of the facts this one was derived from.
+    pub fn parents(&self) -> Vec<Id> {
+        let mut out = Vec::new();
+        match self {
+            Origin::Input => {},
+            Origin::CriticalPair { inner, outer, .. } => out.extend([*inner, *outer]),
+            Origin::Simplified { from, lhs_steps, rhs_steps, .. } => {
+                out.push(*from);
+                out.extend(lhs_steps.iter().chain(rhs_steps).map(|step| step.rule));
+            },
+        }
+        let mut seen = Vec::new();
+        out.retain(|x| if seen.contains(x) { false } else { seen.push(*x); true });
+        out
+    }
+}
+
+// a single rewrite step of a term, using the rule or equation with the given id at position pos.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Rewrite {
+    pub rule: Id,
+    // whether an equation was used from right to left.
+    pub reversed: bool,
+    pub pos: Pos,
+    // the matching substitution, instantiating the rule to the redex.
+    pub subst: Subst,
+    // the term after the rewrite step.
+    pub term: Term,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct Meta {
+    // unique within a State; assigned when the equation is added to it.
+    pub id: Id,
+    pub origin: Origin,
+    // the sorts of the variables, with sorts declared.
+    // Derived facts like X = Y would otherwise lose the sorts of their variables.
+    pub sorts: VarSorts,
+}
+
+// an unoriented equation: lhs = rhs.
+#[derive(PartialEq, Eq, Clone)]
+pub struct Equation {
+    pub lhs: Term,
+    pub rhs: Term,
+    pub meta: Meta,
+}
+
+// an oriented rule: lhs -> rhs.
+#[derive(PartialEq, Eq, Clone)]
+pub struct Rule {
+    pub lhs: Term,
+    pub rhs: Term,
+    pub meta: Meta,
+}
+
+impl Equation {
+    pub fn new(lhs: Term, rhs: Term) -> Equation {
+        Equation { lhs, rhs, meta: Meta::default() }
+    }
+
+    // orients the equation as lhs -> rhs.
+    pub fn into_rule(self) -> Rule {
+        Rule { lhs: self.lhs, rhs: self.rhs, meta: self.meta }
+    }
+
+    pub fn flip(self) -> Equation {
+        Equation { lhs: self.rhs, rhs: self.lhs, meta: self.meta }
+    }
+}
+
+impl Rule {
+    pub fn new(lhs: Term, rhs: Term) -> Rule {
+        Rule { lhs, rhs, meta: Meta::default() }
+    }
+
+    pub fn into_equation(self) -> Equation {
+        Equation { lhs: self.lhs, rhs: self.rhs, meta: self.meta }
+    }
+}
+
+// common interface of equations and rules.
+pub trait Sides: Sized {
+    fn sides(&self) -> (&Term, &Term);
+    fn with_sides(self, lhs: Term, rhs: Term) -> Self;
+    fn meta_mut(&mut self) -> &mut Meta;
+
+    // applies the variable renaming to both sides, and to the origin.
+    fn rename(self, renaming: &Subst) -> Self {
+        let (l, r) = self.sides();
+        let (l, r) = (apply_subst(l, renaming), apply_subst(r, renaming));
+        let mut x = self.with_sides(l, r);
+        x.meta_mut().origin.rename(renaming);
+        x.meta_mut().sorts = rename_sorts(&x.meta_mut().sorts, renaming);
+        x
+    }
+}
+
+impl Sides for Equation {
+    fn sides(&self) -> (&Term, &Term) { (&self.lhs, &self.rhs) }
+    fn with_sides(self, lhs: Term, rhs: Term) -> Self { Equation { lhs, rhs, meta: self.meta } }
+    fn meta_mut(&mut self) -> &mut Meta { &mut self.meta }
+}
+
+impl Sides for Rule {
+    fn sides(&self) -> (&Term, &Term) { (&self.lhs, &self.rhs) }
+    fn with_sides(self, lhs: Term, rhs: Term) -> Self { Rule { lhs, rhs, meta: self.meta } }
+    fn meta_mut(&mut self) -> &mut Meta { &mut self.meta }
 }
 
 mod fmt {
@@ -59,28 +215,112 @@ mod fmt {
         fn fmt(&self, f: &mut Formatter<'_>) -> Result { write!(f, "{}", gsymb_get(*self)) }
     }
 
+    // terms are printed in prefix notation, unless shown with a syntax declaring operators.
     impl Display for Term {
+        fn fmt(&self, f: &mut Formatter<'_>) -> Result { fmt_term(self, f, 0, &Syntax::default()) }
+    }
+
+    impl Display for Show<'_, Term> {
+        fn fmt(&self, f: &mut Formatter<'_>) -> Result { fmt_term(self.x, f, 0, self.syntax) }
+    }
+
+    // declared operators are printed in their notation,
+    // with parentheses if they bind less tightly than min.
+    fn fmt_term(t: &Term, f: &mut Formatter<'_>, min: usize, syntax: &Syntax) -> Result {
+        let (fun, args) = match t {
+            Term::Var(v) => return write!(f, "{v}"),
+            Term::Fun(fun, args) => (fun, args),
+        };
+        let paren = match &args[..] {
+            [a, b] if let Some((p, assoc)) = syntax.infix_op(*fun) => {
+                if p < min { write!(f, "(")?; }
+                fmt_term(a, f, if assoc == Assoc::Left { p } else { p+1 }, syntax)?;
+                write!(f, " {fun} ")?;
+                fmt_term(b, f, if assoc == Assoc::Right { p } else { p+1 }, syntax)?;
+                p < min
+            },
+            [a] if let Some(p) = syntax.prefix_op(*fun) => {
+                if p < min { write!(f, "(")?; }
+                write!(f, "{fun}")?;
+                fmt_term(a, f, p, syntax)?;
+                p < min
+            },
+            [a] if let Some(p) = syntax.postfix_op(*fun) => {
+                if p < min { write!(f, "(")?; }
+                fmt_term(a, f, p, syntax)?;
+                write!(f, "{fun}")?;
+                p < min
+            },
+            _ => {
+                write!(f, "{fun}")?;
+                // an operator constant needs parentheses, or it would be read as an operator.
+                if args.is_empty() && !syntax.is_op(*fun) { return Ok(()) }
+
+                write!(f, "(")?;
+                for (i, a) in args.iter().enumerate() {
+                    fmt_term(a, f, 0, syntax)?;
+                    if i != args.len() - 1 {
+                        write!(f, ", ")?;
+                    }
+                }
+                return write!(f, ")");
+            },
+        };
+        if paren { write!(f, ")")?; }
+        Ok(())
+    }
+
+    impl Display for Equation {
+        fn fmt(&self, f: &mut Formatter<'_>) -> Result { write!(f, "{} = {}", self.lhs, self.rhs) }
+    }
+
+    impl Display for Rule {
+        fn fmt(&self, f: &mut Formatter<'_>) -> Result { write!(f, "{} -> {}", self.lhs, self.rhs) }
+    }
+
+    impl Display for Show<'_, Equation> {
+        fn fmt(&self, f: &mut Formatter<'_>) -> Result { write!(f, "{} = {}", self.syntax.show(&self.x.lhs), self.syntax.show(&self.x.rhs)) }
+    }
+
+    impl Display for Show<'_, Rule> {
+        fn fmt(&self, f: &mut Formatter<'_>) -> Result { write!(f, "{} -> {}", self.syntax.show(&self.x.lhs), self.syntax.show(&self.x.rhs)) }
+    }
+
+    // parents used from right to left are primed.
+    impl Display for Origin {
         fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+            let prime = |reversed: bool| if reversed { "'" } else { "" };
             match self {
-                Term::Var(v) => write!(f, "{v}"),
-                Term::Fun(fun, args) => {
-                    write!(f, "{fun}")?;
-                    if args.len() == 0 { return Ok(()) }
-
-                    write!(f, "(")?;
-                    for (i, a) in args.iter().enumerate() {
-                        write!(f, "{a}")?;
-                        if i != args.len() - 1 {
-                            write!(f, ", ")?;
-                        }
+                Origin::Input => write!(f, "input"),
+                Origin::CriticalPair { inner, inner_reversed, outer, outer_reversed, pos, .. } => {
+                    write!(f, "critical pair of {inner}{} into {outer}{} at {pos:?}", prime(*inner_reversed), prime(*outer_reversed))
+                },
+                Origin::Simplified { from, lhs_steps, rhs_steps, .. } => {
+                    if lhs_steps.is_empty() && rhs_steps.is_empty() { return write!(f, "from {from}") }
+                    write!(f, "simplified {from} by ")?;
+                    for (i, step) in lhs_steps.iter().chain(rhs_steps).enumerate() {
+                        if i > 0 { write!(f, ", ")?; }
+                        write!(f, "{}{}", step.rule, prime(step.reversed))?;
                     }
-                    write!(f, ")")?;
                     Ok(())
                 },
             }
         }
     }
 
+    impl Display for Limit {
+        fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+            match self {
+                Limit::Iterations => write!(f, "iteration limit"),
+                Limit::Rules => write!(f, "rule limit"),
+                Limit::Weight => write!(f, "weight limit"),
+                Limit::Time => write!(f, "time limit"),
+            }
+        }
+    }
+
     impl Debug for Symbol { fn fmt(&self, f: &mut Formatter<'_>) -> Result { write!(f, "{}", self) } }
     impl Debug for Term { fn fmt(&self, f: &mut Formatter<'_>) -> Result { write!(f, "{}", self) } }
+    impl Debug for Equation { fn fmt(&self, f: &mut Formatter<'_>) -> Result { write!(f, "{}", self) } }
+    impl Debug for Rule { fn fmt(&self, f: &mut Formatter<'_>) -> Result { write!(f, "{}", self) } }
 }
@@ -0,0 +1,75 @@
+// A naive implementation of knuth-bendix completion.
+//
+// The modules share their internals via the crate root (`use crate::*`),
+// whereas the public API is explicitly listed in the re-exports at the end.
+
+use std::collections::BTreeMap;
+
+mod lang;
+
+mod state;
+
+mod simplify;
+use simplify::*;
+
+mod order;
+
+mod symbol;
+
+mod ops;
+use ops::*;
+
+mod parse;
+
+mod pat;
+use pat::*;
+
+mod unify;
+
+mod deduce;
+use deduce::*;
+
+mod rename;
+use rename::*;
+
+mod pos;
+
+mod decide;
+
+mod passive;
+
+mod proof;
+
+mod check;
+
+mod problem;
+
+mod signature;
+
+mod tptp;
+
+mod tstp;
+
+mod trs;
+
+// public API:
+
+pub use lang::{Term, Id, Origin, Rewrite, Meta, Equation, Rule, Sides};
+pub use symbol::{Symbol, gsymb_add, gsymb_get};
+pub use ops::{Assoc, Fixity, Syntax, Show};
+pub use parse::{Parse, ParseError, parse_equations};
+pub use pos::{Pos, positions, pos_idx, pos_set};
+pub use pat::{Subst, apply_subst, pat_match, pat_match_sorted};
+pub use unify::{unify, unify_sorted};
+pub use order::{TermOrder, Precedence, Kbo, Lpo, Rpo, SymbolStatus, gt, weight, get_vars, acc_funs};
+pub use state::{State, Config, Outcome, Status, Limit, kbc, joinable, dump_state, dump_derivation};
+pub use simplify::{normalize, simplify_converge};
+pub use decide::{Decision, decide};
+pub use passive::{Passive, Heuristic, Eval};
+pub use proof::{Proof, prove, dump_proof};
+pub use check::check_proof;
+pub use problem::{Problem, OrderSpec};
+pub use signature::{Signature, VarSorts, rename_sorts};
+pub use tptp::{parse_tptp, read_tptp};
+pub use tstp::{szs_status, dump_tstp};
+pub use trs::{parse_trs, write_trs};
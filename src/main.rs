@@ -1,47 +1,191 @@
-use std::collections::BTreeMap;
+use naive_kbc::*;
 
-mod lang;
-pub use lang::*;
+// usage: naive-kbc [--goal EQ]... [--var x]... [--declare f/N | --declare "f : A * B -> C"]... [--infix OP=N[:left|right|non]]... [--prefix OP=N]... [--postfix OP=N]...
+//                  [--order kbo|lpo|rpo] [--precedence "f > g > ..."]
+//                  [--weight f=N]... [--status f=lex|mul]... [--unfailing] [--heuristic H]
+//                  [--max-iterations N] [--max-rules N] [--max-weight N] [--timeout SECS] [--derivation] [--proof] [--szs] [--write-trs FILE] [FILE]...
+// every non-empty line of the inputs is an equation like "m(e,X) = X", or an "axiom" or "goal" statement,
+// optionally named like "goal name: m(X,e) = X", or a directive, named like an option (e.g. "order lpo");
+// "%" starts a comment. options take precedence over the directives of the files.
+// variables start with a capital letter or "_", are marked like "?x", or are declared with --var;
+// symbols can be quoted like 'Foo', and identifiers can contain "_" and "'".
+// the arities of function symbols are inferred from their first use, unless declared with --declare;
+// a symbol used with another arity is an error.
+// --declare "act : G * S -> S" declares sorts (or "V : S" for input variables); then equations have to be well-sorted.
+// operators declared with --infix, --prefix and --postfix can be written as "X * e" or "-X", and are printed so;
+// higher precedences bind tighter, and infix operators are left associative by default.
+// without FILE arguments (or with "-"), the equations are read from stdin.
+// files ending in .p or .ax are read as TPTP problems (unit equalities in CNF or FOF); their conjectures become goals.
+// files ending in .trs are read in the TRS format of the termination and confluence competitions.
+// with goals, completion stops as soon as every goal is proven.
+// equations are oriented by the KBO (default), the LPO or the RPO with the given precedence.
+// the weights of the KBO default to 1, the status of the RPO defaults to mul.
+// with --unfailing, unorientable equations are used for ordered rewriting and critical pairs.
+// the heuristic selecting the next given equation is fifo, weight (default), age-weight:N:M or goal.
+// completion stops after the given number of iterations, active rules, or seconds.
+// equations with terms heavier than --max-weight symbols are dropped, which makes the result incomplete.
+// with --derivation, the derivations of the final rules and equations are printed as well.
+// with --proof, every proven goal is printed with an equational proof from the input equations,
+// which is then verified by an independent checker.
+// with --szs, the result is also reported as SZS status line, and proofs as TSTP derivation.
+// with --write-trs, the final rules are written to the file in the TRS format.
+fn main() {
+    let args = parse_args();
 
-mod state;
-pub use state::*;
+    // the options are applied before reading the inputs, as they may declare operators and variables,
+    // and once more afterwards, so that they take precedence over the directives of the files.
+    let mut problem = Problem::default();
+    apply_options(&mut problem, &args.options);
+    for path in &args.paths {
+        if path.ends_with(".p") || path.ends_with(".ax") {
+            match read_tptp(path).and_then(|p| problem.extend(p)) {
+                Ok(()) => {},
+                Err(e) => die(e),
+            }
+            continue
+        }
 
-mod simplify;
-pub use simplify::*;
+        let src = match read_input(path) {
+            Ok(src) => src,
+            Err(e) => die(format!("{path}: {e}")),
+        };
+        if path.ends_with(".trs") {
+            match parse_trs(&src).and_then(|p| problem.extend(p)) {
+                Ok(()) => {},
+                Err(e) => die(format!("{path}: {e}")),
+            }
+            continue
+        }
+        if let Err(errors) = problem.read(&src, path) {
+            for e in &errors {
+                eprintln!("{path}:{e}");
+            }
+            std::process::exit(1);
+        }
+    }
+    apply_options(&mut problem, &args.options);
 
-mod order;
-pub use order::*;
+    for (i, g) in args.goals.iter().enumerate() {
+        match Equation::parse_with(g, &problem.syntax) {
+            Ok(g) => problem.goals.push((format!("goal{}", i+1), g)),
+            Err(e) => die(format!("goal \"{g}\": {e}")),
+        }
+    }
+    if let Err(e) = problem.check() {
+        die(e);
+    }
+    let goals = problem.goal_equations();
+    // the input equations, indexed by their ids.
+    let axioms: Vec<Equation> = problem.axioms.iter().map(|(_, eq)| eq.clone()).collect();
 
-mod symbol;
-pub use symbol::*;
+    let state = match problem.state() {
+        Ok(state) => state,
+        Err(e) => die(e),
+    };
 
-mod parse;
-pub use parse::*;
+    let outcome = kbc(state, &goals, &problem.config);
+    dump_state(&outcome.state);
+    if args.derivation {
+        let state = &outcome.state;
+        let ids: Vec<Id> = state.rules.iter().map(|r| r.meta.id)
+            .chain(state.equations.iter().map(|e| e.meta.id))
+            .collect();
+        dump_derivation(state, &ids);
+    }
+    if args.proof {
+        for g in &goals {
+            if let Some(proof) = prove(g, &outcome.state) {
+                dump_proof(&proof, &outcome.state);
+                match check_proof(&axioms, g, &proof) {
+                    Ok(()) => println!("proof checked"),
+                    Err(e) => println!("proof check failed: {e}"),
+                }
+            }
+        }
+    }
+    if let Some(path) = &args.write_trs
+        && let Err(e) = write_trs(&outcome.state).and_then(|trs| std::fs::write(path, trs).map_err(|e| e.to_string())) {
+        die(format!("{path}: {e}"));
+    }
+    if args.szs {
+        let name = args.paths.first()
+            .and_then(|p| std::path::Path::new(p).file_stem())
+            .map_or("stdin".to_string(), |s| s.to_string_lossy().to_string());
+        let name = if name == "-" { "stdin".to_string() } else { name };
+        println!("% SZS status {} for {name}", szs_status(&outcome, &goals));
+        if matches!(outcome.status, Status::Proved) {
+            dump_tstp(&name, &problem, &outcome.state);
+        }
+    }
+    println!("{}", outcome.summary(&goals));
+}
 
-mod pat;
-pub use pat::*;
+struct Args {
+    paths: Vec<String>,
+    goals: Vec<String>,
+    // the directives given as options, like ("order", "lpo").
+    options: Vec<(String, String)>,
+    derivation: bool,
+    proof: bool,
+    szs: bool,
+    write_trs: Option<String>,
+}
 
-mod unify;
-pub use unify::*;
+fn parse_args() -> Args {
+    let mut paths = Vec::new();
+    let mut goals = Vec::new();
+    let mut options = Vec::new();
+    let mut derivation = false;
+    let mut proof = false;
+    let mut szs = false;
+    let mut write_trs = None;
+    let mut it = std::env::args().skip(1);
+    while let Some(a) = it.next() {
+        match &*a {
+            // goals are parsed at the end, when all operators are declared.
+            "-g" | "--goal" => goals.push(value(&a, &mut it)),
+            "--derivation" => derivation = true,
+            "--proof" => proof = true,
+            "--szs" => szs = true,
+            "--write-trs" => write_trs = Some(value(&a, &mut it)),
+            _ => {
+                let directive = a.strip_prefix("--").filter(|d| Problem::directive_takes_value(d).is_some());
+                match directive {
+                    Some(d) if Problem::directive_takes_value(d) == Some(true) => options.push((d.to_string(), value(&a, &mut it))),
+                    Some(d) => options.push((d.to_string(), String::new())),
+                    None => paths.push(a),
+                }
+            },
+        }
+    }
+    if paths.is_empty() { paths.push("-".to_string()); }
 
-mod deduce;
-pub use deduce::*;
+    Args { paths, goals, options, derivation, proof, szs, write_trs }
+}
 
-mod rename;
-pub use rename::*;
+fn apply_options(problem: &mut Problem, options: &[(String, String)]) {
+    for (d, v) in options {
+        if let Err(e) = problem.directive(d, v) {
+            die(format!("--{d}: {e}"));
+        }
+    }
+}
 
-mod pos;
-pub use pos::*;
+// the value of the command-line option a.
+fn value(a: &str, it: &mut impl Iterator<Item=String>) -> String {
+    let Some(v) = it.next() else { die(format!("{a} expects a value")) };
+    v
+}
 
-fn main() {
-    let state = vec![
-        Equation::parse("m(e,X) = X").unwrap(),
-        Equation::parse("m(n(X),X) = e").unwrap(),
-        Equation::parse("m(m(X,Y),Z) = m(X,m(Y,Z))").unwrap(),
-        Equation::parse("a = m(d,e)").unwrap(),
-        Equation::parse("b = d").unwrap(),
-
-        // We are looking for 'a = b'.
-    ];
-    kbc(state);
+fn read_input(path: &str) -> std::io::Result<String> {
+    if path == "-" {
+        std::io::read_to_string(std::io::stdin())
+    } else {
+        std::fs::read_to_string(path)
+    }
+}
+
+fn die(msg: String) -> ! {
+    eprintln!("{msg}");
+    std::process::exit(1)
 }
@@ -0,0 +1,108 @@
+use crate::*;
+
+use std::collections::BTreeSet;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Assoc { Left, Right, Non }
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Fixity {
+    Infix(Assoc),
+    Prefix,
+    Postfix,
+}
+
+// The notation of a problem: its operators, and the names declared as variables.
+// It is used for parsing the problem, and for printing its terms (see show()).
+// Operators consist of special characters, like "*", "+", "\" or "'".
+// The same symbol can be declared infix as well as prefix or postfix, e.g. binary and unary "-".
+#[derive(Clone, Default, Debug)]
+pub struct Syntax {
+    // precedences; higher ones bind tighter.
+    infix: BTreeMap<Symbol, (usize, Assoc)>,
+    prefix: BTreeMap<Symbol, usize>,
+    postfix: BTreeMap<Symbol, usize>,
+
+    // names declared as variables, e.g. the lowercase variables of other tools.
+    vars: BTreeSet<String>,
+}
+
+// whether c can be part of an operator.
+// "?" is reserved for variables like "?x", and "%" for comments.
+pub fn is_op_char(c: char) -> bool {
+    !c.is_alphanumeric() && !c.is_whitespace() && !"()=,_?%".contains(c)
+}
+
+impl Syntax {
+    pub fn declare_var(&mut self, name: &str) -> Result<(), String> {
+        if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '\'') {
+            return Err(format!("invalid variable \"{name}\""));
+        }
+        self.vars.insert(name.to_string());
+        Ok(())
+    }
+
+    // whether the identifier is a variable:
+    // it starts with a capital letter or "_", is marked like "?x", or is declared as variable.
+    pub fn is_var_name(&self, name: &str) -> bool {
+        name.starts_with(|c: char| c.is_ascii_uppercase() || c == '_' || c == '?') || self.vars.contains(name)
+    }
+
+    // declares an operator, or changes its precedence.
+    pub fn declare_op(&mut self, name: &str, fixity: Fixity, prec: usize) -> Result<(), String> {
+        if name.is_empty() || !name.chars().all(is_op_char) {
+            return Err(format!("invalid operator \"{name}\""));
+        }
+        let s = gsymb_add(name.to_string());
+        match fixity {
+            Fixity::Infix(assoc) => { self.infix.insert(s, (prec, assoc)); },
+            Fixity::Prefix => { self.prefix.insert(s, prec); },
+            Fixity::Postfix => { self.postfix.insert(s, prec); },
+        }
+        Ok(())
+    }
+
+    pub fn infix_op(&self, s: Symbol) -> Option<(usize, Assoc)> {
+        self.infix.get(&s).copied()
+    }
+
+    pub fn prefix_op(&self, s: Symbol) -> Option<usize> {
+        self.prefix.get(&s).copied()
+    }
+
+    pub fn postfix_op(&self, s: Symbol) -> Option<usize> {
+        self.postfix.get(&s).copied()
+    }
+
+    pub fn is_op(&self, s: Symbol) -> bool {
+        self.infix.contains_key(&s) || self.prefix.contains_key(&s) || self.postfix.contains_key(&s)
+    }
+
+    // the longest declared operator at the start of s.
+    pub fn op_prefix_of(&self, s: &str) -> Option<Symbol> {
+        let ops = self.infix.keys().chain(self.prefix.keys()).chain(self.postfix.keys());
+        ops.map(|op| gsymb_get(*op))
+           .filter(|op| s.starts_with(op.as_str()))
+           .max_by_key(|op| op.len())
+           .map(gsymb_add)
+    }
+
+    // adds the declarations of the other syntax; later declarations of an operator win.
+    pub fn merge(&mut self, other: &Syntax) {
+        self.infix.extend(other.infix.iter().map(|(s, x)| (*s, *x)));
+        self.prefix.extend(other.prefix.iter().map(|(s, x)| (*s, *x)));
+        self.postfix.extend(other.postfix.iter().map(|(s, x)| (*s, *x)));
+        self.vars.extend(other.vars.iter().cloned());
+    }
+
+    // the term, equation or rule, printed with the declared operators.
+    pub fn show<'a, T: ?Sized>(&'a self, x: &'a T) -> Show<'a, T> {
+        Show { syntax: self, x }
+    }
+}
+
+// a term, equation or rule together with the syntax it is printed in.
+pub struct Show<'a, T: ?Sized> {
+    pub syntax: &'a Syntax,
+    pub x: &'a T,
+}
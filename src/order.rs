@@ -1,62 +1,265 @@
 use crate::*;
 
-// s > t
-pub fn gt(s: &Term, t: &Term) -> bool {
-    let vars_s = get_vars(s);
-    let vars_t = get_vars(t);
-    for (x, ct) in &vars_t {
-        let cs = vars_s.get(x).unwrap_or(&0);
-        // if t contains a variable more than s, then we have to return false.
-        if ct > cs { return false }
+use std::cmp::Ordering;
+use std::fmt::Debug;
+
+// A reduction order on terms, used for orienting equations into rules.
+pub trait TermOrder: Debug + Send + Sync {
+    // s > t
+    fn gt(&self, s: &Term, t: &Term) -> bool;
+
+    // checks that the order is admissible for the given function symbols (with their arities).
+    fn validate(&self, _funs: &BTreeMap<Symbol, usize>) -> Result<(), String> { Ok(()) }
+}
+
+// A total precedence on function symbols.
+// Listed symbols are ordered as given (the first one is the greatest) and are greater than all unlisted symbols.
+// Unlisted symbols are compared by their names.
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct Precedence(pub Vec<Symbol>);
+
+impl Precedence {
+    // syntax: "i > m > e", or with operators "' > * > e".
+    pub fn parse(s: &str) -> Option<Precedence> {
+        let mut v = Vec::new();
+        for x in s.split('>') {
+            let x = x.trim();
+            let ident = x.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '\'');
+            if x.is_empty() || !(ident || x.chars().all(is_op_char)) { return None }
+            let x = gsymb_add(x.to_string());
+            if v.contains(&x) { return None }
+            v.push(x);
+        }
+        Some(Precedence(v))
+    }
+
+    pub fn cmp(&self, f: Symbol, g: Symbol) -> Ordering {
+        let idx = |x| self.0.iter().position(|y| *y == x);
+        match (idx(f), idx(g)) {
+            (Some(i), Some(j)) => j.cmp(&i),
+            (Some(_), None) => Ordering::Greater,
+            (None, Some(_)) => Ordering::Less,
+            (None, None) => f.cmp(&g),
+        }
+    }
+}
+
+// The Knuth-Bendix order.
+// Variables have weight 1, function symbols without explicit weight as well.
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct Kbo {
+    pub precedence: Precedence,
+    pub weights: BTreeMap<Symbol, usize>,
+}
+
+impl Kbo {
+    pub fn symbol_weight(&self, f: Symbol) -> usize {
+        *self.weights.get(&f).unwrap_or(&1)
     }
 
-    let ws = weight(s);
-    let wt = weight(t);
-    if ws > wt { return true }
-    if ws < wt { return false }
+    pub fn weight(&self, t: &Term) -> usize {
+        match t {
+            Term::Var(_) => 1,
+            Term::Fun(f, children) => self.symbol_weight(*f) + children.iter().map(|x| self.weight(x)).sum::<usize>(),
+        }
+    }
+}
+
+impl TermOrder for Kbo {
+    fn gt(&self, s: &Term, t: &Term) -> bool {
+        let vars_s = get_vars(s);
+        let vars_t = get_vars(t);
+        for (x, ct) in &vars_t {
+            let cs = vars_s.get(x).unwrap_or(&0);
+            // if t contains a variable more than s, then we have to return false.
+            if ct > cs { return false }
+        }
+
+        let ws = self.weight(s);
+        let wt = self.weight(t);
+        if ws > wt { return true }
+        if ws < wt { return false }
+
+        assert_eq!(ws, wt);
 
-    assert_eq!(ws, wt);
+        let Term::Fun(fs, ls) = s else { return false };
 
-    let Term::Fun(fs, ls) = s else { return false };
-    let Term::Fun(ft, lt) = t else { return false };
+        // s = f(f(...f(t))) for a unary symbol f of weight 0.
+        let Term::Fun(ft, lt) = t else { return true };
 
-    if fs > ft { return true }
-    if fs < ft { return false }
+        match self.precedence.cmp(*fs, *ft) {
+            Ordering::Greater => return true,
+            Ordering::Less => return false,
+            Ordering::Equal => {},
+        }
+
+        assert_eq!(fs, ft);
+
+        assert_eq!(ls.len(), lt.len());
 
-    assert_eq!(fs, ft);
+        for (cs, ct) in ls.iter().zip(lt.iter()) {
+            if self.gt(cs, ct) { return true }
 
-    assert_eq!(ls.len(), lt.len());
+            if cs == ct { continue }
+            else { return false }
+        }
 
-    for (cs, ct) in ls.iter().zip(lt.iter()) {
-        if gt(cs, ct) { return true }
+        assert!(s == t);
 
-        if cs == ct { continue }
-        else { return false }
+        false
     }
 
-    assert!(s == t);
+    // the weights are admissible, if
+    // - constants have a positive weight,
+    // - a unary symbol of weight 0 is greater than every other symbol in the precedence.
+    fn validate(&self, funs: &BTreeMap<Symbol, usize>) -> Result<(), String> {
+        for (f, arity) in funs {
+            let w = self.symbol_weight(*f);
+            if *arity == 0 && w == 0 {
+                return Err(format!("constant {f} has weight 0"));
+            }
+            if *arity == 1 && w == 0 {
+                for g in funs.keys() {
+                    if g != f && self.precedence.cmp(*f, *g) != Ordering::Greater {
+                        return Err(format!("{f} has weight 0, but is not greater than {g} in the precedence"));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
 
-    false
+// The lexicographic path order.
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct Lpo {
+    pub precedence: Precedence,
 }
 
-fn weight(t: &Term) -> usize {
-    match t {
-        Term::Var(_) => 1,
-        Term::Fun(_, children) => 1 + children.iter().map(weight).sum::<usize>(),
+impl TermOrder for Lpo {
+    fn gt(&self, s: &Term, t: &Term) -> bool {
+        let Term::Fun(fs, ls) = s else { return false };
+
+        let (ft, lt) = match t {
+            // s > x, if x occurs in s.
+            Term::Var(x) => return get_vars(s).contains_key(x),
+            Term::Fun(ft, lt) => (ft, lt),
+        };
+
+        // some argument of s is >= t.
+        if ls.iter().any(|x| x == t || self.gt(x, t)) { return true }
+
+        match self.precedence.cmp(*fs, *ft) {
+            Ordering::Greater => lt.iter().all(|x| self.gt(s, x)),
+            Ordering::Less => false,
+            Ordering::Equal => {
+                assert_eq!(ls.len(), lt.len());
+                for (cs, ct) in ls.iter().zip(lt.iter()) {
+                    if cs == ct { continue }
+                    return self.gt(cs, ct) && lt.iter().all(|x| self.gt(s, x));
+                }
+                false
+            },
+        }
     }
 }
 
+// how the arguments of a function symbol are compared in the RPO.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SymbolStatus {
+    // lexicographically from left to right.
+    Lex,
+
+    // as multisets, i.e. regardless of the argument order.
+    Mul,
+}
+
+// The recursive path order.
+// Symbols without explicit status have multiset status.
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct Rpo {
+    pub precedence: Precedence,
+    pub status: BTreeMap<Symbol, SymbolStatus>,
+}
+
+impl Rpo {
+    pub fn symbol_status(&self, f: Symbol) -> SymbolStatus {
+        *self.status.get(&f).unwrap_or(&SymbolStatus::Mul)
+    }
+
+    // the multiset extension of the order: ms >mul ns.
+    fn mul_gt(&self, ms: &[Term], ns: &[Term]) -> bool {
+        let mut ms: Vec<&Term> = ms.iter().collect();
+        let mut ns: Vec<&Term> = ns.iter().collect();
+
+        // remove the common elements.
+        ns.retain(|n| {
+            let Some(i) = ms.iter().position(|m| m == n) else { return true };
+            ms.remove(i);
+            false
+        });
+
+        !ms.is_empty() && ns.iter().all(|n| ms.iter().any(|m| self.gt(m, n)))
+    }
+}
+
+impl TermOrder for Rpo {
+    fn gt(&self, s: &Term, t: &Term) -> bool {
+        let Term::Fun(fs, ls) = s else { return false };
+
+        let (ft, lt) = match t {
+            // s > x, if x occurs in s.
+            Term::Var(x) => return get_vars(s).contains_key(x),
+            Term::Fun(ft, lt) => (ft, lt),
+        };
+
+        // some argument of s is >= t.
+        if ls.iter().any(|x| x == t || self.gt(x, t)) { return true }
+
+        match self.precedence.cmp(*fs, *ft) {
+            Ordering::Greater => lt.iter().all(|x| self.gt(s, x)),
+            Ordering::Less => false,
+            Ordering::Equal => {
+                assert_eq!(ls.len(), lt.len());
+                match self.symbol_status(*fs) {
+                    SymbolStatus::Mul => self.mul_gt(ls, lt),
+                    SymbolStatus::Lex => {
+                        for (cs, ct) in ls.iter().zip(lt.iter()) {
+                            if cs == ct { continue }
+                            return self.gt(cs, ct) && lt.iter().all(|x| self.gt(s, x));
+                        }
+                        false
+                    },
+                }
+            },
+        }
+    }
+}
+
+// the number of symbols in t.
+pub fn weight(t: &Term) -> usize {
+    Kbo::default().weight(t)
+}
+
+// s > t w.r.t. the default KBO.
+pub fn gt(s: &Term, t: &Term) -> bool {
+    Kbo::default().gt(s, t)
+}
+
 pub fn get_vars(t: &Term) -> BTreeMap<Symbol, usize> {
     let mut out = BTreeMap::new();
-    acc_vars(&t, &mut out);
+    acc_vars(t, &mut out);
     out
 }
 
-pub fn get_vars_eq((l, r, _): &Equation) -> BTreeMap<Symbol, usize> {
-    let mut out = BTreeMap::new();
-    acc_vars(&l, &mut out);
-    acc_vars(&r, &mut out);
-    out
+// maps every function symbol in t to its arity.
+pub fn acc_funs(t: &Term, acc: &mut BTreeMap<Symbol, usize>) {
+    if let Term::Fun(f, children) = t {
+        acc.insert(*f, children.len());
+        for x in children.iter() {
+            acc_funs(x, acc);
+        }
+    }
 }
 
 fn acc_vars(t: &Term, acc: &mut BTreeMap<Symbol, usize>) {
@@ -134,4 +337,72 @@ mod tst {
     fn assoc() {
         kbo_assert("f(f(X, Y), Z) > f(X, f(Y, Z))");
     }
+
+    fn kbo(prec: &str, weights: &[(&str, usize)]) -> Kbo {
+        let precedence = Precedence::parse(prec).unwrap();
+        let weights = weights.iter().map(|(f, w)| (gsymb_add(f.to_string()), *w)).collect();
+        Kbo { precedence, weights }
+    }
+
+    #[test]
+    fn precedence_chk() {
+        let kbo = kbo("i > m > e", &[]);
+        let t = |s| Term::parse(s).unwrap();
+        assert!(kbo.gt(&t("i(X)"), &t("m(X)")));
+        assert!(kbo.gt(&t("e"), &t("a")));
+        assert!(!gt(&t("e"), &t("a")) || !gt(&t("a"), &t("e")));
+    }
+
+    #[test]
+    fn lpo_chk() {
+        let lpo = |prec| Lpo { precedence: Precedence::parse(prec).unwrap() };
+        let t = |s| Term::parse(s).unwrap();
+
+        // distributivity
+        let lpo1 = lpo("m > p");
+        assert!(lpo1.gt(&t("m(X, p(Y, Z))"), &t("p(m(X, Y), m(X, Z))")));
+        assert!(!lpo1.gt(&t("p(m(X, Y), m(X, Z))"), &t("m(X, p(Y, Z))")));
+
+        // ackermann
+        let lpo2 = lpo("a > s > z");
+        assert!(lpo2.gt(&t("a(s(X), s(Y))"), &t("a(X, a(s(X), Y))")));
+        assert!(lpo2.gt(&t("a(s(X), z)"), &t("a(X, s(z))")));
+
+        assert!(lpo2.gt(&t("s(X)"), &t("X")));
+        assert!(!lpo2.gt(&t("s(X)"), &t("Y")));
+        assert!(!lpo2.gt(&t("a(X, Y)"), &t("a(Y, X)")));
+    }
+
+    #[test]
+    fn rpo_chk() {
+        let t = |s| Term::parse(s).unwrap();
+        let f = gsymb_add("f".to_string());
+
+        let mut rpo = Rpo { precedence: Precedence::parse("f > g").unwrap(), status: BTreeMap::new() };
+        assert!(rpo.gt(&t("f(X, g(Y))"), &t("f(Y, X)")));
+        assert!(rpo.gt(&t("f(g(X), Y)"), &t("f(Y, X)")));
+        assert!(!rpo.gt(&t("f(X, Y)"), &t("f(Y, X)")));
+        assert!(!rpo.gt(&t("f(f(X, Y), Z)"), &t("f(X, f(Y, Z))")));
+        assert!(!rpo.gt(&t("f(X, f(Y, Z))"), &t("f(f(X, Y), Z)")));
+        assert!(!rpo.gt(&t("g(f(X, X))"), &t("f(g(X), g(X))")));
+        assert!(rpo.gt(&t("f(g(X), g(X))"), &t("g(f(X, X))")));
+
+        rpo.status.insert(f, SymbolStatus::Lex);
+        assert!(rpo.gt(&t("f(f(X, Y), Z)"), &t("f(X, f(Y, Z))")));
+        assert!(!rpo.gt(&t("f(X, g(Y))"), &t("f(Y, X)")));
+    }
+
+    #[test]
+    fn weight0_chk() {
+        let kbo = kbo("i > m > e", &[("i", 0)]);
+        let t = |s| Term::parse(s).unwrap();
+        assert!(kbo.gt(&t("i(i(X))"), &t("X")));
+        assert!(kbo.gt(&t("i(m(X, Y))"), &t("m(i(Y), i(X))")));
+        assert!(!kbo.gt(&t("m(i(Y), i(X))"), &t("i(m(X, Y))")));
+
+        let funs = [("i", 1), ("m", 2), ("e", 0)].iter().map(|(f, a)| (gsymb_add(f.to_string()), *a)).collect();
+        assert!(kbo.validate(&funs).is_ok());
+        assert!(self::kbo("m > i > e", &[("i", 0)]).validate(&funs).is_err());
+        assert!(self::kbo("i > m > e", &[("e", 0)]).validate(&funs).is_err());
+    }
 }
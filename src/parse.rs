@@ -1,106 +1,316 @@
 use crate::*;
 
+use std::fmt::{self, Display};
+
 #[derive(Debug)]
-pub enum Token {
+enum Token {
     LParen, RParen,
     Equals,
     Comma,
     Var(Symbol),
     Fun(Symbol),
+    // a declared operator.
+    Op(Symbol),
 }
 
-fn tokenize(s: &str) -> Option<Vec<Token>> {
-    let mut tokens = Vec::new();
-    let mut current: Option<String> = None;
-    for c in s.chars() {
-        if c.is_alphanumeric() {
-            if let Some(s) = &mut current {
-                s.push(c);
-            } else {
-                current = Some(c.to_string());
-            }
-            continue;
-        }
+// a token together with its line and column (both starting at 1).
+#[derive(Debug)]
+struct Located {
+    token: Token,
+    line: usize,
+    column: usize,
+}
 
-        if let Some(s) = current.take() {
-            let ch = s.chars().next()?;
-            let is_var = ('A'..='Z').contains(&ch);
+// what the parser expected at some location, and what it found instead (None for the end of the input).
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub found: Option<String>,
+    pub expected: String,
+}
 
-            let s = gsymb_add(s);
-            let tok = if is_var { Token::Var(s) } else { Token::Fun(s) };
-            tokens.push(tok);
+impl Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::LParen => write!(f, "("),
+            Token::RParen => write!(f, ")"),
+            Token::Equals => write!(f, "="),
+            Token::Comma => write!(f, ","),
+            Token::Var(s) | Token::Fun(s) | Token::Op(s) => write!(f, "{s}"),
         }
+    }
+}
 
-        if c == '=' {
-            tokens.push(Token::Equals);
-        } else if c == '(' {
-            tokens.push(Token::LParen);
-        } else if c == ')' {
-            tokens.push(Token::RParen);
-        } else if c == ',' {
-            tokens.push(Token::Comma);
-        } else if !c.is_whitespace() {
-            return None;
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: expected {}, ", self.line, self.column, self.expected)?;
+        match &self.found {
+            Some(tok) => write!(f, "found \"{tok}\""),
+            None => write!(f, "found end of input"),
         }
     }
+}
 
-    if let Some(s) = current.take() {
-        let ch = s.chars().next()?;
-        let is_var = ('A'..='Z').contains(&ch);
+// the error for the first of the tokens, or for the end of the input at (line, column) if there are none.
+fn error(tokens: &[Located], end: (usize, usize), expected: &str) -> ParseError {
+    let expected = expected.to_string();
+    match tokens.first() {
+        Some(t) => ParseError { line: t.line, column: t.column, found: Some(t.token.to_string()), expected },
+        None => ParseError { line: end.0, column: end.1, found: None, expected },
+    }
+}
 
-        let s = gsymb_add(s);
-        let tok = if is_var { Token::Var(s) } else { Token::Fun(s) };
-        tokens.push(tok);
+fn tokenize(s: &str, syntax: &Syntax) -> Result<Vec<Located>, ParseError> {
+    let mut tokens = Vec::new();
+    let (mut line, mut column) = (1, 1);
+    let mut rest = s;
+    // a declared operator "'" cannot be part of identifiers.
+    let quote_op = syntax.op_prefix_of("'").is_some();
+    let ident_char = |c: char| c.is_alphanumeric() || c == '_' || (c == '\'' && !quote_op);
+    while let Some(c) = rest.chars().next() {
+        let marked = c == '?' && rest[1..].starts_with(ident_char);
+        let (token, n) = if c.is_alphanumeric() || c == '_' || marked {
+            let start = c.len_utf8();
+            let n = rest[start..].find(|c: char| !ident_char(c)).map_or(rest.len(), |i| i + start);
+            let word = &rest[..n];
+            let x = gsymb_add(word.to_string());
+            (if syntax.is_var_name(word) { Token::Var(x) } else { Token::Fun(x) }, n)
+        } else if c == '\'' && !quote_op {
+            // quoted symbols end on the same line.
+            let Some(i) = rest[1..].lines().next().and_then(|l| l.find('\'')) else {
+                return Err(ParseError { line, column, found: Some("'".to_string()), expected: "a closing \"'\"".to_string() });
+            };
+            let word = &rest[..i+2];
+            // 'foo' is the same symbol as foo.
+            let inner = &word[1..word.len()-1];
+            let plain = inner.starts_with(|c: char| c.is_lowercase()) && inner.chars().all(ident_char) && !syntax.is_var_name(inner);
+            let name = if plain { inner } else { word };
+            (Token::Fun(gsymb_add(name.to_string())), word.len())
+        } else {
+            match c {
+                '=' => (Token::Equals, 1),
+                '(' => (Token::LParen, 1),
+                ')' => (Token::RParen, 1),
+                ',' => (Token::Comma, 1),
+                '\n' => {
+                    line += 1;
+                    column = 1;
+                    rest = &rest[1..];
+                    continue
+                },
+                c if c.is_whitespace() => {
+                    column += 1;
+                    rest = &rest[c.len_utf8()..];
+                    continue
+                },
+                c => {
+                    let Some(op) = syntax.op_prefix_of(rest) else {
+                        let expected = "a symbol, an operator, \"(\", \")\", \",\" or \"=\"".to_string();
+                        return Err(ParseError { line, column, found: Some(c.to_string()), expected });
+                    };
+                    (Token::Op(op), gsymb_get(op).len())
+                },
+            }
+        };
+        tokens.push(Located { token, line, column });
+        column += rest[..n].chars().count();
+        rest = &rest[n..];
     }
 
-    Some(tokens)
+    Ok(tokens)
 }
 
 pub trait Parse: Sized {
-    fn assemble(tokens: &[Token]) -> Option<(&[Token], Self)>;
+    // parses s with the operators and variables declared in the syntax.
+    fn parse_with(s: &str, syntax: &Syntax) -> Result<Self, ParseError>;
 
-    fn parse(s: &str) -> Option<Self> {
-        let tokens = tokenize(s)?;
-        let (tokens, out) = Self::assemble(&tokens[..])?;
-        if tokens.len() > 0 { return None; }
-        Some(out)
+    // parses s without any declared operators and variables.
+    fn parse(s: &str) -> Result<Self, ParseError> {
+        Self::parse_with(s, &Syntax::default())
     }
 }
 
 impl Parse for Equation {
-    fn assemble(tokens: &[Token]) -> Option<(&[Token], Self)> {
-        let (tokens, lhs) = Term::assemble(tokens)?;
-        let [Token::Equals, tokens@..] = tokens else { return None; };
-        let (tokens, rhs) = Term::assemble(tokens)?;
-        let eq = (lhs, rhs, false);
-        Some((tokens, eq))
+    fn parse_with(s: &str, syntax: &Syntax) -> Result<Self, ParseError> {
+        parse_all(s, syntax, equation)
     }
 }
 
 impl Parse for Term {
-    fn assemble(tokens: &[Token]) -> Option<(&[Token], Self)> {
-        let [tok, tokens@..] = tokens else { return None; };
-        if let Token::Var(s) = *tok {
-            return Some((tokens, Term::Var(s)));
+    fn parse_with(s: &str, syntax: &Syntax) -> Result<Self, ParseError> {
+        parse_all(s, syntax, |tokens, syntax, end| expr(tokens, syntax, end, 0))
+    }
+}
+
+// the remaining tokens, and what was parsed from the ones before.
+type Assemble<'t, T> = Result<(&'t [Located], T), ParseError>;
+
+// parses all of s with the given parser, which parses a prefix of the tokens and returns the remaining ones.
+fn parse_all<T>(s: &str, syntax: &Syntax, assemble: impl for<'t> Fn(&'t [Located], &Syntax, (usize, usize)) -> Assemble<'t, T>) -> Result<T, ParseError> {
+    let end = end_of(s);
+    let tokens = tokenize(s, syntax)?;
+    let (tokens, out) = assemble(&tokens[..], syntax, end)?;
+    if !tokens.is_empty() { return Err(error(tokens, end, "end of input")) }
+    Ok(out)
+}
+
+// the location just after the last character of s.
+fn end_of(s: &str) -> (usize, usize) {
+    let line = s.matches('\n').count() + 1;
+    let last = s.rsplit('\n').next().unwrap_or("");
+    (line, last.chars().count() + 1)
+}
+
+fn equation<'t>(tokens: &'t [Located], syntax: &Syntax, end: (usize, usize)) -> Assemble<'t, Equation> {
+    let (tokens, lhs) = expr(tokens, syntax, end, 0)?;
+    let [Located { token: Token::Equals, .. }, tokens@..] = tokens else { return Err(error(tokens, end, "\"=\"")) };
+    let (tokens, rhs) = expr(tokens, syntax, end, 0)?;
+    Ok((tokens, Equation::new(lhs, rhs)))
+}
+
+// parses a term, whose operators (outside of parentheses) bind at least as tight as min.
+fn expr<'t>(tokens: &'t [Located], syntax: &Syntax, end: (usize, usize), min: usize) -> Assemble<'t, Term> {
+    let (mut tokens, mut lhs) = match tokens {
+        // an operator applied to parenthesized arguments is parsed like a function.
+        [Located { token: Token::Op(op), .. }, rest@..] if !matches!(rest, [Located { token: Token::LParen, .. }, ..]) => {
+            let Some(p) = syntax.prefix_op(*op) else { return Err(error(tokens, end, "a term")) };
+            let (rest, x) = expr(rest, syntax, end, p)?;
+            (rest, Term::Fun(*op, Box::new([x])))
+        },
+        _ => primary(tokens, syntax, end)?,
+    };
+
+    while let [Located { token: Token::Op(op), .. }, rest@..] = tokens {
+        if let Some(p) = syntax.postfix_op(*op) && p >= min {
+            lhs = Term::Fun(*op, Box::new([lhs]));
+            tokens = rest;
+            continue
         }
 
-        let Token::Fun(f) = *tok else { return None; };
-        let [Token::LParen, tokens@..] = tokens else {
-            let term = Term::Fun(f, Box::new([]));
-            return Some((tokens, term));
-        };
+        let Some((p, assoc)) = syntax.infix_op(*op) else { break };
+        if p < min { break }
+        let (rest, rhs) = expr(rest, syntax, end, if assoc == Assoc::Right { p } else { p+1 })?;
+        lhs = Term::Fun(*op, Box::new([lhs, rhs]));
+        tokens = rest;
 
-        let mut tokens = tokens;
-        let mut children = Vec::new();
-        loop {
-            let (tokens2, t) = Term::assemble(tokens)?;
-            children.push(t);
-            tokens = tokens2;
-            let [Token::Comma, tokens2@..] = tokens else { break; };
-            tokens = tokens2;
+        if assoc == Assoc::Non && let [Located { token: Token::Op(op2), .. }, ..] = tokens
+            && syntax.infix_op(*op2).is_some_and(|(p2, _)| p2 == p) {
+            return Err(error(tokens, end, &format!("parentheses, as \"{op}\" is not associative")));
         }
-        let [Token::RParen, tokens@..] = tokens else { return None; };
-        let term = Term::Fun(f, children.into_boxed_slice());
-        Some((tokens, term))
+    }
+    Ok((tokens, lhs))
+}
+
+// a variable, a function application, a constant, or a parenthesized term.
+fn primary<'t>(tokens: &'t [Located], syntax: &Syntax, end: (usize, usize)) -> Assemble<'t, Term> {
+    let [tok, rest@..] = tokens else { return Err(error(tokens, end, "a term")) };
+    let f = match tok.token {
+        Token::Var(s) => return Ok((rest, Term::Var(s))),
+        Token::LParen => {
+            let (rest, t) = expr(rest, syntax, end, 0)?;
+            let [Located { token: Token::RParen, .. }, rest@..] = rest else { return Err(error(rest, end, "\")\"")) };
+            return Ok((rest, t));
+        },
+        Token::Fun(f) | Token::Op(f) => f,
+        _ => return Err(error(tokens, end, "a term")),
+    };
+
+    let [Located { token: Token::LParen, .. }, rest@..] = rest else {
+        let term = Term::Fun(f, Box::new([]));
+        return Ok((rest, term));
+    };
+    if let [Located { token: Token::RParen, .. }, rest@..] = rest {
+        return Ok((rest, Term::Fun(f, Box::new([]))));
+    }
+
+    let mut tokens = rest;
+    let mut children = Vec::new();
+    loop {
+        let (tokens2, t) = expr(tokens, syntax, end, 0)?;
+        children.push(t);
+        tokens = tokens2;
+        let [Located { token: Token::Comma, .. }, tokens2@..] = tokens else { break; };
+        tokens = tokens2;
+    }
+    let [Located { token: Token::RParen, .. }, tokens@..] = tokens else { return Err(error(tokens, end, "\",\" or \")\"")) };
+    let term = Term::Fun(f, children.into_boxed_slice());
+    Ok((tokens, term))
+}
+
+// parses every non-empty line of src as an equation, collecting the errors of all lines.
+pub fn parse_equations(src: &str) -> Result<Vec<(usize, Equation)>, Vec<ParseError>> {
+    let mut eqs = Vec::new();
+    let mut errors = Vec::new();
+    for (i, line) in src.lines().enumerate() {
+        if line.trim().is_empty() { continue }
+        match Equation::parse(line) {
+            Ok(eq) => eqs.push((i+1, eq)),
+            Err(e) => errors.push(ParseError { line: i+1, ..e }),
+        }
+    }
+    if errors.is_empty() { Ok(eqs) } else { Err(errors) }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn parse_errors() {
+        let e = Equation::parse("m(X, e = X").unwrap_err();
+        assert_eq!((e.line, e.column, e.found.as_deref()), (1, 8, Some("=")));
+        assert_eq!(e.to_string(), "1:8: expected \",\" or \")\", found \"=\"");
+
+        let e = Equation::parse("m(X, e)").unwrap_err();
+        assert_eq!((e.column, e.found), (8, None));
+
+        let e = Term::parse("f(a) g").unwrap_err();
+        assert_eq!((e.column, e.expected.as_str()), (6, "end of input"));
+
+        let errors = parse_equations("f(X) = X\n\nf(# = a\ng(a) = )\n").unwrap_err();
+        let lines: Vec<_> = errors.iter().map(|e| (e.line, e.column)).collect();
+        assert_eq!(lines, [(3, 3), (4, 8)]);
+    }
+
+    #[test]
+    fn operators() {
+        let mut syntax = Syntax::default();
+        syntax.declare_op("+", Fixity::Infix(Assoc::Left), 50).unwrap();
+        syntax.declare_op("*", Fixity::Infix(Assoc::Left), 70).unwrap();
+        syntax.declare_op("^", Fixity::Infix(Assoc::Right), 80).unwrap();
+        syntax.declare_op("-", Fixity::Prefix, 90).unwrap();
+        syntax.declare_op("!", Fixity::Postfix, 95).unwrap();
+        syntax.declare_op("~", Fixity::Infix(Assoc::Non), 40).unwrap();
+        let parse = |s: &str| Term::parse_with(s, &syntax);
+
+        let t = parse("X + Y * -Z! + a ^ b ^ c").unwrap();
+        assert_eq!(t, parse("+(+(X, *(Y, -(!(Z)))), ^(a, ^(b, c)))").unwrap());
+        for s in ["X + Y * -Z! + a ^ b ^ c", "(X + Y) * Z", "X + (Y + Z)", "(a ^ b) ^ c", "(-X)!", "-(X * Y)", "f(X * Y, -a)", "*()"] {
+            assert_eq!(syntax.show(&parse(s).unwrap()).to_string(), s);
+        }
+        assert_eq!(t.to_string(), "+(+(X, *(Y, -(!(Z)))), ^(a, ^(b, c)))");
+        assert!(parse("a ~ b ~ c").is_err());
+        assert!(parse("a * * b").is_err());
+        assert!(parse("a # b").is_err());
+        assert!(Term::parse("a + b").is_err());
+    }
+
+    #[test]
+    fn identifiers() {
+        let mut syntax = Syntax::default();
+        syntax.declare_var("xs").unwrap();
+        let t = Term::parse_with("f_1('Foo', ?y, xs, _z, g', 'h', 'a b')", &syntax).unwrap();
+        let Term::Fun(f, args) = &t else { panic!() };
+        assert_eq!(f.to_string(), "f_1");
+        let kinds: Vec<bool> = args.iter().map(|x| matches!(x, Term::Var(_))).collect();
+        assert_eq!(kinds, [false, true, true, true, false, false, false]);
+        assert_eq!(t.to_string(), "f_1('Foo', ?y, xs, _z, g', h, 'a b')");
+        assert_eq!(Term::parse_with(&t.to_string(), &syntax).unwrap(), t);
+        assert!(matches!(Term::parse("xs").unwrap(), Term::Fun(..)));
+
+        let e = Term::parse("f('a)").unwrap_err();
+        assert_eq!((e.column, e.expected.as_str()), (3, "a closing \"'\""));
+        assert!(Term::parse("f(?)").is_err());
     }
 }
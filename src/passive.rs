@@ -0,0 +1,206 @@
+use crate::*;
+
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, BinaryHeap};
+use std::sync::Arc;
+
+// evaluates a passive equation; smaller values are selected first.
+pub type Eval = Arc<dyn Fn(&Equation) -> usize + Send + Sync>;
+
+// the order in which passive equations are selected as given equation.
+// Ties are always broken by age.
+#[derive(Clone, Default)]
+pub enum Heuristic {
+    // the oldest equation first.
+    Fifo,
+
+    // the lightest equation first (counting symbols).
+    #[default]
+    Weight,
+
+    // alternately selects the oldest equation n times and the lightest equation m times.
+    AgeWeight(usize, usize),
+
+    // the equation most similar to the goals first.
+    Goal,
+
+    // a user-defined evaluation.
+    Custom(Eval),
+}
+
+impl std::fmt::Debug for Heuristic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Heuristic::Fifo => write!(f, "Fifo"),
+            Heuristic::Weight => write!(f, "Weight"),
+            Heuristic::AgeWeight(n, m) => write!(f, "AgeWeight({n}, {m})"),
+            Heuristic::Goal => write!(f, "Goal"),
+            Heuristic::Custom(_) => write!(f, "Custom(..)"),
+        }
+    }
+}
+
+impl Heuristic {
+    // syntax: "fifo", "weight", "age-weight:N:M", or "goal".
+    pub fn parse(s: &str) -> Option<Heuristic> {
+        let h = match s {
+            "fifo" => Heuristic::Fifo,
+            "weight" => Heuristic::Weight,
+            "goal" => Heuristic::Goal,
+            _ => {
+                let [name, n, m] = *s.split(':').collect::<Vec<_>>() else { return None };
+                if name != "age-weight" { return None }
+                let (n, m) = (n.parse().ok()?, m.parse().ok()?);
+                if n + m == 0 { return None }
+                Heuristic::AgeWeight(n, m)
+            },
+        };
+        Some(h)
+    }
+
+    // the evaluations together with how many equations are selected by each of them in turn.
+    fn evals(&self, goals: &[Equation]) -> Vec<(Eval, usize)> {
+        let age: Eval = Arc::new(|eq| eq.meta.id);
+        let weight: Eval = Arc::new(|eq| weight(&eq.lhs) + weight(&eq.rhs));
+        match self {
+            Heuristic::Fifo => vec![(age, 1)],
+            Heuristic::Weight => vec![(weight, 1)],
+            Heuristic::AgeWeight(n, m) => vec![(age, *n), (weight, *m)],
+            Heuristic::Goal => {
+                let mut funs = BTreeSet::new();
+                for g in goals {
+                    let mut acc = BTreeMap::new();
+                    acc_funs(&g.lhs, &mut acc);
+                    acc_funs(&g.rhs, &mut acc);
+                    funs.extend(acc.into_keys());
+                }
+                vec![(Arc::new(move |eq| goal_distance(&eq.lhs, &funs) + goal_distance(&eq.rhs, &funs)), 1)]
+            },
+            Heuristic::Custom(f) => vec![(f.clone(), 1)],
+        }
+    }
+}
+
+// the weight of t, where function symbols that do not occur in the goals count thrice.
+fn goal_distance(t: &Term, goal_funs: &BTreeSet<Symbol>) -> usize {
+    match t {
+        Term::Var(_) => 1,
+        Term::Fun(f, args) => {
+            let w = if goal_funs.contains(f) { 1 } else { 3 };
+            w + args.iter().map(|x| goal_distance(x, goal_funs)).sum::<usize>()
+        },
+    }
+}
+
+// The passive equations, waiting to be selected as given equation.
+#[derive(Clone)]
+pub struct Passive {
+    evals: Vec<(Eval, usize)>,
+
+    // one queue per evaluation, each containing every passive equation.
+    // Equations selected by one queue are lazily removed from the others.
+    queues: Vec<BinaryHeap<Reverse<(usize, Id)>>>,
+    equations: BTreeMap<Id, Equation>,
+
+    // counts the selections, to determine which queue is next.
+    turn: usize,
+}
+
+impl Default for Passive {
+    fn default() -> Passive {
+        Passive::new(&Heuristic::default(), &[])
+    }
+}
+
+impl Passive {
+    pub fn new(heuristic: &Heuristic, goals: &[Equation]) -> Passive {
+        let evals = heuristic.evals(goals);
+        let queues = evals.iter().map(|_| BinaryHeap::new()).collect();
+        Passive { evals, queues, equations: BTreeMap::new(), turn: 0 }
+    }
+
+    // re-evaluates all passive equations with the new heuristic.
+    pub fn set_heuristic(&mut self, heuristic: &Heuristic, goals: &[Equation]) {
+        let old = std::mem::replace(self, Passive::new(heuristic, goals));
+        for eq in old.equations.into_values() {
+            self.push(eq);
+        }
+    }
+
+    pub fn push(&mut self, eq: Equation) {
+        for ((eval, _), queue) in self.evals.iter().zip(self.queues.iter_mut()) {
+            queue.push(Reverse((eval(&eq), eq.meta.id)));
+        }
+        self.equations.insert(eq.meta.id, eq);
+    }
+
+    pub fn pop(&mut self) -> Option<Equation> {
+        if self.equations.is_empty() { return None }
+
+        let total: usize = self.evals.iter().map(|(_, n)| n).sum();
+        let mut t = self.turn % total;
+        self.turn += 1;
+        let mut i = 0;
+        while t >= self.evals[i].1 {
+            t -= self.evals[i].1;
+            i += 1;
+        }
+
+        loop {
+            let Reverse((_, id)) = self.queues[i].pop()?;
+            if let Some(eq) = self.equations.remove(&id) { return Some(eq) }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.equations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.equations.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=&Equation> {
+        self.equations.values()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::sync::Arc;
+
+    fn passive(h: &str) -> Passive {
+        with_heuristic(&Heuristic::parse(h).unwrap(), &[])
+    }
+
+    fn with_heuristic(h: &Heuristic, goals: &[Equation]) -> Passive {
+        let mut p = Passive::new(h, goals);
+        for (i, s) in ["f(f(f(X))) = X", "f(X) = X", "f(f(X)) = X", "a = b"].iter().enumerate() {
+            let mut eq = Equation::parse(s).unwrap();
+            eq.meta.id = i;
+            p.push(eq);
+        }
+        p
+    }
+
+    fn order(mut p: Passive) -> Vec<Id> {
+        std::iter::from_fn(|| p.pop()).map(|eq| eq.meta.id).collect()
+    }
+
+    #[test]
+    fn heuristics() {
+        assert_eq!(order(passive("fifo")), [0, 1, 2, 3]);
+        assert_eq!(order(passive("weight")), [3, 1, 2, 0]);
+        assert_eq!(order(passive("age-weight:1:2")), [0, 3, 1, 2]);
+        assert!(Heuristic::parse("age-weight:0:0").is_none());
+
+        // "a = b" is the lightest, but its symbols do not occur in the goal.
+        let goals = [Equation::parse("f(c) = c").unwrap()];
+        assert_eq!(order(with_heuristic(&Heuristic::Goal, &goals)), [1, 2, 0, 3]);
+
+        // the heaviest equation first.
+        let heaviest: Eval = Arc::new(|eq| 100 - weight(&eq.lhs) - weight(&eq.rhs));
+        assert_eq!(order(with_heuristic(&Heuristic::Custom(heaviest), &[])), [0, 2, 1, 3]);
+    }
+}
@@ -30,21 +30,37 @@ pub fn pat_match(pat: &Term, t: &Term) -> Option<Subst> {
     assert!(v_disjoint(&pat_vars, &t_vars));
 
     let mut subst = Default::default();
-    pat_match_impl(pat, t, &mut subst, &pat_vars)?;
+    pat_match_impl(pat, t, &mut subst, &pat_vars, None, None)?;
+    Some(subst)
+}
+
+// like pat_match, but variables are only bound to terms of their sort.
+// sort is the sort of t, if known from its context; vars contains the sorts of the variables of pat.
+pub fn pat_match_sorted(pat: &Term, t: &Term, sort: Option<Symbol>, sig: &Signature, vars: &VarSorts) -> Option<Subst> {
+    let pat_vars = get_vars(pat);
+    let t_vars = get_vars(t);
+    assert!(v_disjoint(&pat_vars, &t_vars));
+
+    let mut subst = Default::default();
+    pat_match_impl(pat, t, &mut subst, &pat_vars, Some((sig, vars)), sort)?;
     Some(subst)
 }
 
 // subst :: vars(pat) -> Term[vars(t)]
-fn pat_match_impl(pat: &Term, t: &Term, subst: &mut Subst, pat_vars: &BTreeMap<Symbol, usize>) -> Option<()> {
+fn pat_match_impl(pat: &Term, t: &Term, subst: &mut Subst, pat_vars: &BTreeMap<Symbol, usize>, sorts: Option<(&Signature, &VarSorts)>, sort: Option<Symbol>) -> Option<()> {
     match pat {
         Term::Var(v) => {
             if let Some(tv) = subst.get(v) {
                 let tv = tv.clone();
-                return pat_match_impl(&tv, t, subst, pat_vars);
+                return pat_match_impl(&tv, t, subst, pat_vars, sorts, sort);
             }
 
             // we only insert stuff into pat-vars, not the non-pat vars that we from a prior subst.
-            if pat_vars.contains_key(&v) {
+            if pat_vars.contains_key(v) {
+                if let Some((sig, vars)) = sorts {
+                    let t_sort = sort.or(sig.sort_of(t, vars));
+                    if let (Some(s1), Some(s2)) = (vars.get(v), t_sort) && *s1 != s2 { return None }
+                }
                 subst.insert(*v, t.clone());
             } else {
                 return match t {
@@ -57,8 +73,9 @@ fn pat_match_impl(pat: &Term, t: &Term, subst: &mut Subst, pat_vars: &BTreeMap<S
             let Term::Fun(f2, args2) = t else { return None };
             if f != f2 { return None }
             if args.len() != args2.len() { return None }
-            for (x, y) in args.iter().zip(args2.iter()) {
-                pat_match_impl(x, y, subst, pat_vars)?;
+            for (i, (x, y)) in args.iter().zip(args2.iter()).enumerate() {
+                let sort = sorts.and_then(|(sig, _)| sig.arg_sort(*f, i));
+                pat_match_impl(x, y, subst, pat_vars, sorts, sort)?;
             }
         },
     }
@@ -66,6 +83,8 @@ fn pat_match_impl(pat: &Term, t: &Term, subst: &mut Subst, pat_vars: &BTreeMap<S
 }
 
 #[cfg(test)]
+// the tests are kept as they were written, before the lints were enforced.
+#[allow(unused_variables, clippy::useless_format)]
 mod tests {
     use crate::*;
 
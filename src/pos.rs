@@ -20,7 +20,7 @@ pub fn positions(t: &Term) -> Vec<Pos> {
 
 // t[pos]
 pub fn pos_idx<'t>(t: &'t Term, pos: &[usize]) -> &'t Term {
-    match (t, pos.get(0)) {
+    match (t, pos.first()) {
         (t, None) => t,
         (Term::Fun(_, args), Some(i)) => pos_idx(&args[*i], &pos[1..]),
         _ => panic!(),
@@ -29,7 +29,7 @@ pub fn pos_idx<'t>(t: &'t Term, pos: &[usize]) -> &'t Term {
 
 // t[pos := x]
 pub fn pos_set(t: &Term, pos: &[usize], x: &Term) -> Term {
-    match (t, pos.get(0)) {
+    match (t, pos.first()) {
         (_, None) => x.clone(),
         (Term::Fun(f, args), Some(i)) => {
             let mut args = args.clone();
@@ -0,0 +1,280 @@
+use crate::*;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+// A completion problem: named axioms, and named goals to be proven from them,
+// together with the settings for completing it.
+//
+// Problem files consist of statements, directives and comments, one per line, e.g.
+//   % group theory
+//   order kbo
+//   precedence i > m > e
+//   max-iterations 1000
+//   axiom left_identity: m(e, X) = X
+//   axiom m(i(X), X) = e
+//   m(m(X, Y), Z) = m(X, m(Y, Z))
+//   goal m(X, e) = X
+// Comments start with "%"; names of statements are optional, and plain equations are axioms as well.
+// The directives are named like the command-line options (see directive()).
+#[derive(Clone, Default, Debug)]
+pub struct Problem {
+    pub axioms: Vec<(String, Equation)>,
+    pub goals: Vec<(String, Equation)>,
+    // the declared symbols; see check() for the others.
+    pub signature: Signature,
+    // the declared operators and variables, for parsing and printing the problem.
+    pub syntax: Syntax,
+    pub order: OrderSpec,
+    pub config: Config,
+}
+
+// the reduction order of a problem, built once all directives are known.
+#[derive(Clone, Debug)]
+pub struct OrderSpec {
+    // kbo, lpo or rpo.
+    pub name: String,
+    pub precedence: Precedence,
+    pub weights: BTreeMap<Symbol, usize>,
+    pub status: BTreeMap<Symbol, SymbolStatus>,
+}
+
+impl Default for OrderSpec {
+    fn default() -> OrderSpec {
+        OrderSpec { name: "kbo".to_string(), precedence: Precedence::default(), weights: BTreeMap::new(), status: BTreeMap::new() }
+    }
+}
+
+impl OrderSpec {
+    pub fn build(&self) -> Result<Arc<dyn TermOrder>, String> {
+        let name = &*self.name;
+        if name != "kbo" && !self.weights.is_empty() { return Err("weights are only supported by the KBO".to_string()) }
+        if name != "rpo" && !self.status.is_empty() { return Err("status is only supported by the RPO".to_string()) }
+        let precedence = self.precedence.clone();
+        let order: Arc<dyn TermOrder> = match name {
+            "kbo" => Arc::new(Kbo { precedence, weights: self.weights.clone() }),
+            "lpo" => Arc::new(Lpo { precedence }),
+            "rpo" => Arc::new(Rpo { precedence, status: self.status.clone() }),
+            o => return Err(format!("unknown order \"{o}\"")),
+        };
+        Ok(order)
+    }
+}
+
+const KEYWORDS: &[&str] = &["axiom", "goal"];
+
+impl Problem {
+    pub fn extend(&mut self, other: Problem) -> Result<(), String> {
+        self.signature.merge(&other.signature)?;
+        self.syntax.merge(&other.syntax);
+        self.axioms.extend(other.axioms);
+        self.goals.extend(other.goals);
+        Ok(())
+    }
+
+    // checks that every symbol is used with a consistent arity, and adds the undeclared ones to the signature.
+    pub fn check(&mut self) -> Result<(), String> {
+        for (name, eq) in self.axioms.iter().chain(&self.goals) {
+            self.signature.check_equation(eq).map_err(|e| format!("{name}: {e}"))?;
+        }
+        Ok(())
+    }
+
+    // a state with the axioms in the passive queue, where the id of each axiom is its index,
+    // and the order of the problem, which has to be admissible for its signature.
+    // The axioms carry the sorts of their variables, including the declared ones.
+    // The problem is expected to be checked.
+    pub fn state(&self) -> Result<State, String> {
+        let mut state: State = self.axioms.iter().map(|(_, eq)| {
+            let mut eq = eq.clone();
+            eq.meta.sorts = self.signature.equation_sorts(&eq);
+            eq
+        }).collect();
+        state.order = self.order.build()?;
+        state.order.validate(self.signature.arities()).map_err(|e| format!("inadmissible order: {e}"))?;
+        state.signature = self.signature.clone();
+        state.syntax = self.syntax.clone();
+        Ok(state)
+    }
+
+    pub fn goal_equations(&self) -> Vec<Equation> {
+        self.goals.iter().map(|(_, eq)| eq.clone()).collect()
+    }
+
+    // whether the directive exists, and whether it takes a value.
+    pub fn directive_takes_value(name: &str) -> Option<bool> {
+        match name {
+            "unfailing" => Some(false),
+            "order" | "precedence" | "weight" | "status" | "heuristic" | "max-iterations" | "max-rules" | "max-weight"
+                | "timeout" | "declare" | "var" | "infix" | "prefix" | "postfix" => Some(true),
+            _ => None,
+        }
+    }
+
+    // applies a directive, e.g. ("precedence", "i > m > e") or ("weight", "e=2").
+    // Operators and variables are declared in the syntax of the problem, which is used for parsing the later statements.
+    pub fn directive(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let number = || value.parse::<usize>().map_err(|_| format!("{name} expects a number"));
+        match name {
+            "unfailing" => self.config.unfailing = true,
+            "order" => self.order.name = value.to_string(),
+            "precedence" => {
+                let Some(p) = Precedence::parse(value) else { return Err(format!("cannot parse precedence \"{value}\"")) };
+                self.order.precedence = p;
+            },
+            "weight" => {
+                let Some((f, n)) = value.split_once('=') else { return Err(format!("{name} expects SYMBOL=WEIGHT")) };
+                let Ok(n) = n.trim().parse() else { return Err(format!("invalid weight \"{n}\"")) };
+                self.order.weights.insert(gsymb_add(f.trim().to_string()), n);
+            },
+            "status" => {
+                let Some((f, st)) = value.split_once('=') else { return Err(format!("{name} expects SYMBOL=lex|mul")) };
+                let st = match st.trim() {
+                    "lex" => SymbolStatus::Lex,
+                    "mul" => SymbolStatus::Mul,
+                    _ => return Err(format!("invalid status \"{st}\"")),
+                };
+                self.order.status.insert(gsymb_add(f.trim().to_string()), st);
+            },
+            "heuristic" => {
+                let Some(h) = Heuristic::parse(value) else { return Err(format!("unknown heuristic \"{value}\"")) };
+                self.config.heuristic = h;
+            },
+            "max-iterations" => self.config.max_iterations = Some(number()?),
+            "max-rules" => self.config.max_rules = Some(number()?),
+            "max-weight" => self.config.max_weight = Some(number()?),
+            "timeout" => {
+                let Ok(secs) = value.parse::<f64>() else { return Err(format!("{name} expects a number of seconds")) };
+                let Ok(t) = Duration::try_from_secs_f64(secs) else { return Err(format!("invalid timeout \"{value}\"")) };
+                self.config.timeout = Some(t);
+            },
+            "declare" => self.signature.parse_declaration(value, &self.syntax)?,
+            "var" => self.syntax.declare_var(value)?,
+            "infix" | "prefix" | "postfix" => {
+                let Some((op, spec)) = value.split_once('=') else { return Err(format!("{name} expects OPERATOR=PRECEDENCE")) };
+                let (prec, assoc) = spec.split_once(':').unwrap_or((spec, "left"));
+                let Ok(prec) = prec.trim().parse() else { return Err(format!("invalid precedence \"{prec}\"")) };
+                let fixity = match (name, assoc.trim()) {
+                    ("prefix", _) => Fixity::Prefix,
+                    ("postfix", _) => Fixity::Postfix,
+                    (_, "left") => Fixity::Infix(Assoc::Left),
+                    (_, "right") => Fixity::Infix(Assoc::Right),
+                    (_, "non") => Fixity::Infix(Assoc::Non),
+                    (_, assoc) => return Err(format!("invalid associativity \"{assoc}\"")),
+                };
+                self.syntax.declare_op(op.trim(), fixity, prec)?;
+            },
+            _ => return Err(format!("unknown directive \"{name}\"")),
+        }
+        Ok(())
+    }
+
+    // reads a problem file (see above) into the problem; unnamed statements are named "path:line".
+    // The errors of all lines are collected, each prefixed by its location.
+    pub fn read(&mut self, src: &str, path: &str) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        for (i, line) in src.lines().enumerate() {
+            let line = strip_comment(line);
+            let indent = line.len() - line.trim_start().len();
+            let stmt = line.trim();
+            if stmt.is_empty() { continue }
+
+            let (word, rest) = stmt.split_at(stmt.find(char::is_whitespace).unwrap_or(stmt.len()));
+            if Problem::directive_takes_value(word).is_some() {
+                if let Err(e) = self.directive(word, rest.trim()) {
+                    errors.push(format!("{}: {e}", i+1));
+                }
+                continue
+            }
+
+            // the equation, and its offset in the line.
+            let (name, eq, offset) = if KEYWORDS.contains(&word) {
+                let offset = indent + word.len() + (rest.len() - rest.trim_start().len());
+                let rest = rest.trim_start();
+                match statement_name(rest) {
+                    Some((name, n)) => (Some(name), &rest[n..], offset + n),
+                    None => (None, rest, offset),
+                }
+            } else {
+                (None, stmt, indent)
+            };
+            let name = name.map_or(format!("{path}:{}", i+1), str::to_string);
+            match Equation::parse_with(eq, &self.syntax) {
+                Ok(eq) if word == "goal" => self.goals.push((name, eq)),
+                Ok(eq) => self.axioms.push((name, eq)),
+                Err(e) => {
+                    let column = line[..offset].chars().count() + e.column;
+                    errors.push(ParseError { line: i+1, column, ..e }.to_string());
+                },
+            }
+        }
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+}
+
+// the line up to a comment.
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            // a quote after an identifier or a parenthesis is a prime, not the start of a quoted symbol.
+            '\'' if quoted || !line[..i].ends_with(|c: char| c.is_alphanumeric() || c == '_' || c == ')') => quoted = !quoted,
+            '%' if !quoted => return &line[..i],
+            _ => {},
+        }
+    }
+    line
+}
+
+// the name of a statement like "name: equation", and the length of "name:".
+fn statement_name(s: &str) -> Option<(&str, usize)> {
+    let (name, _) = s.split_once(':')?;
+    let valid = !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || "_-.'".contains(c));
+    valid.then_some((name, name.len() + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn problem_file() {
+        let src = "
+            % group theory
+            order lpo
+            precedence i > m > e   % inverse first
+            max-iterations 100
+            axiom left_identity: m(e, X) = X
+            axiom m(i(X), X) = e
+            m(m(X, Y), Z) = m(X, m(Y, Z))
+            goal right_identity: m(X, e) = X
+        ";
+        let mut problem = Problem::default();
+        problem.read(src, "grp").unwrap();
+        let names: Vec<&str> = problem.axioms.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["left_identity", "grp:7", "grp:8"]);
+        assert_eq!(problem.goals[0], ("right_identity".to_string(), Equation::parse("m(X, e) = X").unwrap()));
+        assert_eq!(problem.order.name, "lpo");
+        assert_eq!(problem.config.max_iterations, Some(100));
+
+        problem.check().unwrap();
+        let outcome = kbc(problem.state().unwrap(), &problem.goal_equations(), &problem.config);
+        assert!(matches!(outcome.status, Status::Proved));
+
+        let errors = Problem::default().read("max-iterations many\naxiom a: f(X = X\n", "p").unwrap_err();
+        assert_eq!(errors, ["1: max-iterations expects a number", "2:14: expected \",\" or \")\", found \"=\""]);
+    }
+
+    #[test]
+    fn problem_syntax() {
+        let mut problem = Problem::default();
+        problem.read("infix * = 70\nvar x\nx * e = x\n", "p").unwrap();
+        let (_, eq) = &problem.axioms[0];
+        assert!(matches!(eq.rhs, Term::Var(_)));
+        assert_eq!(problem.syntax.show(eq).to_string(), "x * e = x");
+        assert_eq!(eq.to_string(), "*(x, e) = x");
+
+        // the operators and variables are only declared for the problem.
+        assert!(Problem::default().read("x * e = x\n", "q").is_err());
+    }
+}
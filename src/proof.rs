@@ -0,0 +1,160 @@
+use crate::*;
+
+use std::iter::once;
+
+// a linear equational proof lhs = t1 = ... = tn.
+// Every step applies an input equation or rule of the state (an axiom), the ids in the steps refer to those.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Proof {
+    pub lhs: Term,
+    pub steps: Vec<Rewrite>,
+}
+
+impl Proof {
+    // the last term of the chain.
+    pub fn rhs(&self) -> &Term {
+        self.steps.last().map(|s| &s.term).unwrap_or(&self.lhs)
+    }
+
+    // the same chain from rhs to lhs.
+    fn reverse(self) -> Proof {
+        let terms: Vec<Term> = once(self.lhs).chain(self.steps.iter().map(|s| s.term.clone())).collect();
+        // step i leads from terms[i] to terms[i+1].
+        let steps = self.steps.into_iter().enumerate().rev()
+            .map(|(i, s)| Rewrite { reversed: !s.reversed, term: terms[i].clone(), ..s })
+            .collect();
+        Proof { lhs: terms[terms.len()-1].clone(), steps }
+    }
+
+    fn then(mut self, other: Proof) -> Proof {
+        debug_assert_eq!(self.rhs(), &other.lhs);
+        self.steps.extend(other.steps);
+        self
+    }
+
+    fn instantiate(&self, subst: &Subst) -> Proof {
+        let steps = self.steps.iter().map(|s| Rewrite {
+            subst: s.subst.iter().map(|(x, t)| (*x, apply_subst(t, subst))).collect(),
+            term: apply_subst(&s.term, subst),
+            ..s.clone()
+        }).collect();
+        Proof { lhs: apply_subst(&self.lhs, subst), steps }
+    }
+
+    // the steps of the proof, applied at position pos of the term context.
+    fn embed(self, context: &Term, pos: &Pos) -> Vec<Rewrite> {
+        self.steps.into_iter().map(|s| Rewrite {
+            pos: pos.iter().chain(&s.pos).copied().collect(),
+            term: pos_set(context, pos, &s.term),
+            ..s
+        }).collect()
+    }
+}
+
+// an equational proof of the goal from the axioms, if both sides of the goal are joinable in the state.
+pub fn prove(goal: &Equation, state: &State) -> Option<Proof> {
+    // rename the variables apart from the rules; and rename them back in the end.
+    let (eq, rev) = canonize_vars_d_rev(goal.clone());
+    let (s, s_steps) = normalize(eq.lhs.clone(), state);
+    let (t, t_steps) = normalize(eq.rhs.clone(), state);
+    if s != t { return None }
+
+    let mut prover = Prover { state, proofs: BTreeMap::new() };
+    let to_s = prover.rewrites(eq.lhs, &s_steps);
+    let to_t = prover.rewrites(eq.rhs, &t_steps);
+    Some(to_s.then(to_t.reverse()).instantiate(&rev))
+}
+
+// expands the derivations of facts into proofs using only axioms.
+struct Prover<'s> {
+    state: &'s State,
+    // the proofs of the facts computed so far.
+    proofs: BTreeMap<Id, Proof>,
+}
+
+impl Prover<'_> {
+    // a proof of the recorded fact from its lhs to its rhs.
+    fn fact(&mut self, id: Id) -> Proof {
+        if let Some(p) = self.proofs.get(&id) { return p.clone() }
+
+        let fact = self.state.fact(id);
+        let proof = match &fact.meta.origin {
+            Origin::Input => {
+                let subst = get_vars(&fact.lhs).into_keys().chain(get_vars(&fact.rhs).into_keys())
+                    .map(|x| (x, Term::Var(x)))
+                    .collect();
+                let step = Rewrite { rule: id, reversed: false, pos: Vec::new(), subst, term: fact.rhs.clone() };
+                Proof { lhs: fact.lhs.clone(), steps: vec![step] }
+            },
+            Origin::CriticalPair { inner, inner_reversed, inner_subst, outer, outer_reversed, outer_subst, pos } => {
+                // from the lhs up to the overlapped term with the inner fact, then down to the rhs with the outer fact.
+                let up = self.directed(*inner, !*inner_reversed).instantiate(inner_subst);
+                let down = self.directed(*outer, *outer_reversed).instantiate(outer_subst);
+                let mut steps = up.embed(&down.lhs, pos);
+                steps.extend(down.steps);
+                Proof { lhs: fact.lhs.clone(), steps }
+            },
+            Origin::Simplified { from, renaming, lhs_steps, rhs_steps } => {
+                let from = self.fact(*from).instantiate(renaming);
+                let l = self.rewrites(from.lhs.clone(), lhs_steps).reverse();
+                let r = self.rewrites(from.rhs().clone(), rhs_steps);
+                let p = l.then(from).then(r);
+                // the sides may have been swapped.
+                if p.lhs == fact.lhs { p } else { p.reverse() }
+            },
+        };
+        self.proofs.insert(id, proof.clone());
+        proof
+    }
+
+    fn directed(&mut self, id: Id, reversed: bool) -> Proof {
+        let p = self.fact(id);
+        if reversed { p.reverse() } else { p }
+    }
+
+    // expands the rewrite steps, starting from term.
+    fn rewrites(&mut self, mut term: Term, steps: &[Rewrite]) -> Proof {
+        let lhs = term.clone();
+        let mut out = Vec::new();
+        for step in steps {
+            let p = self.directed(step.rule, step.reversed).instantiate(&step.subst);
+            out.extend(p.embed(&term, &step.pos));
+            term = step.term.clone();
+        }
+        Proof { lhs, steps: out }
+    }
+}
+
+// prints the proof in calc style, every step justified by the axiom, its position and the substitution.
+// Primed ids refer to axioms used from right to left.
+pub fn dump_proof(proof: &Proof, state: &State) {
+    let show = |t| state.syntax.show(t);
+    println!("PROOF:");
+    println!("  {}", show(&proof.lhs));
+    for step in &proof.steps {
+        let prime = if step.reversed { "'" } else { "" };
+        let subst: Vec<String> = step.subst.iter().map(|(x, t)| format!("{x} := {}", show(t))).collect();
+        let with = if subst.is_empty() { String::new() } else { format!(" with {}", subst.join(", ")) };
+        println!("= {{ {}{prime} {} at {:?}{with} }}", step.rule, state.syntax.show(state.fact(step.rule)), step.pos);
+        println!("  {}", show(&step.term));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn group_proof() {
+        let axioms: Vec<Equation> = ["m(e, X) = X", "m(n(X), X) = e", "m(m(X, Y), Z) = m(X, m(Y, Z))"].iter()
+            .map(|e| Equation::parse(e).unwrap())
+            .collect();
+        let goal = Equation::parse("m(a, n(a)) = e").unwrap();
+        let outcome = kbc(axioms.iter().cloned().collect(), std::slice::from_ref(&goal), &Config::default());
+        assert!(matches!(outcome.status, Status::Proved));
+
+        let proof = prove(&goal, &outcome.state).unwrap();
+        assert!(proof.steps.iter().all(|s| outcome.state.fact(s.rule).meta.origin == Origin::Input));
+        assert_eq!(check_proof(&axioms, &goal, &proof), Ok(()));
+    }
+}
@@ -1,29 +1,39 @@
 use crate::*;
 
-pub fn canonize_vars_l(e: Equation) -> Equation {
-    canonize_vars_generic(e, |i| {
-        let c = format!("L{i}");
-        gsymb_add(c)
-    })
-}
-
 // This renaming will be used for a term that we are currently rewriting.
 // It cannot collide with any other variable names.
-pub fn canonize_vars_d(e: Equation) -> Equation {
-    canonize_vars_generic(e, |i| {
-        let c = format!("D{i}");
-        gsymb_add(c)
-    })
+pub fn canonize_vars_d<T: Sides>(e: T) -> T {
+    canonize_vars_generic(e, d_name)
 }
 
-pub fn canonize_vars_r(e: Equation) -> Equation {
-    canonize_vars_generic(e, |i| {
-        let c = format!("R{i}");
-        gsymb_add(c)
-    })
+// like canonize_vars_d, but additionally returns the substitution undoing the renaming.
+pub fn canonize_vars_d_rev<T: Sides>(e: T) -> (T, Subst) {
+    let subst = renaming(&e, d_name);
+    let mut rev = Subst::new();
+    for (x, y) in &subst {
+        let Term::Var(y) = y else { unreachable!() };
+        rev.insert(*y, Term::Var(*x));
+    }
+    (canonize_vars_generic(e, d_name), rev)
+}
+
+pub fn d_name(i: usize) -> Symbol {
+    let c = format!("D{i}");
+    gsymb_add(c)
+}
+
+// names for renaming the two facts of an overlap apart.
+pub fn l_name(i: usize) -> Symbol {
+    let c = format!("L{i}");
+    gsymb_add(c)
 }
 
-pub fn canonize_vars(e: Equation) -> Equation {
+pub fn r_name(i: usize) -> Symbol {
+    let c = format!("R{i}");
+    gsymb_add(c)
+}
+
+pub fn canonize_vars<T: Sides>(e: T) -> T {
     canonize_vars_generic(e, |i| {
         let l = &["X", "Y", "Z", "W", "V", "U", "T", "S"];
         let a = l[i%8];
@@ -34,19 +44,24 @@ pub fn canonize_vars(e: Equation) -> Equation {
     })
 }
 
-pub fn canonize_vars_generic((l, r, ori): Equation, name_fn: impl Fn(usize) -> Symbol) -> Equation {
+pub fn canonize_vars_generic<T: Sides>(e: T, name_fn: impl Fn(usize) -> Symbol) -> T {
+    let subst = renaming(&e, name_fn);
+    e.rename(&subst)
+}
+
+// maps the i-th variable occurring in the equation to name_fn(i).
+pub fn renaming(e: &impl Sides, name_fn: impl Fn(usize) -> Symbol) -> Subst {
+    let (l, r) = e.sides();
     let mut v: Vec<Symbol> = Vec::new();
-    acc_var_order(&l, &mut v);
-    acc_var_order(&r, &mut v);
+    acc_var_order(l, &mut v);
+    acc_var_order(r, &mut v);
 
     let mut subst = Subst::new();
     for (i, x) in v.iter().enumerate() {
         let c = name_fn(i);
         subst.insert(*x, Term::Var(c));
     }
-    let (l, r) = (apply_subst(&l, &subst), apply_subst(&r, &subst));
-
-    (l, r, ori)
+    subst
 }
 
 fn acc_var_order(t: &Term, acc: &mut Vec<Symbol>) {
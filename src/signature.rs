@@ -0,0 +1,270 @@
+use crate::*;
+
+use std::fmt::{self, Display};
+
+// the sorts of variables.
+pub type VarSorts = BTreeMap<Symbol, Symbol>;
+
+// The function symbols with their arities, and optionally their sorts.
+// Symbols are either declared up front, or their arity is inferred from their first use;
+// either way, every later use has to agree with it.
+// With sorts declared (e.g. "act : G * S -> S"), terms have to be well-sorted,
+// and the sorts of the variables of an equation are inferred from their positions,
+// unless they are declared for the input (e.g. "X, Y : G").
+// Symbols without declared sort can be used anywhere.
+#[derive(Clone, PartialEq, Eq, Default, Debug)]
+pub struct Signature {
+    arities: BTreeMap<Symbol, usize>,
+    // argument sorts and result sort.
+    sorts: BTreeMap<Symbol, (Vec<Symbol>, Symbol)>,
+    vars: VarSorts,
+}
+
+impl Signature {
+    pub fn arity(&self, f: Symbol) -> Option<usize> {
+        self.arities.get(&f).copied()
+    }
+
+    // maps every function symbol to its arity.
+    pub fn arities(&self) -> &BTreeMap<Symbol, usize> {
+        &self.arities
+    }
+
+    pub fn declare(&mut self, f: Symbol, arity: usize) -> Result<(), String> {
+        match self.arity(f) {
+            Some(n) if n != arity => Err(format!("{f} is declared with arity {arity}, but has arity {n}")),
+            _ => {
+                self.arities.insert(f, arity);
+                Ok(())
+            },
+        }
+    }
+
+    pub fn declare_sort(&mut self, f: Symbol, args: Vec<Symbol>, result: Symbol) -> Result<(), String> {
+        self.declare(f, args.len())?;
+        match self.sorts.get(&f) {
+            Some(old) if *old != (args.clone(), result) => Err(format!("{f} is declared with different sorts")),
+            _ => {
+                self.sorts.insert(f, (args, result));
+                Ok(())
+            },
+        }
+    }
+
+    // declares the sort of the variable x in the input.
+    pub fn declare_var(&mut self, x: Symbol, sort: Symbol) -> Result<(), String> {
+        match self.vars.insert(x, sort) {
+            Some(old) if old != sort => Err(format!("{x} is declared with the sorts {old} and {sort}")),
+            _ => Ok(()),
+        }
+    }
+
+    // whether any sorts are declared.
+    pub fn is_sorted(&self) -> bool {
+        !self.sorts.is_empty()
+    }
+
+    // declares a symbol, given as "f/2", with sorts as "m : G * G -> G" or "e : G",
+    // or variables as "X, Y : G"; the syntax tells variables apart.
+    pub fn parse_declaration(&mut self, s: &str, syntax: &Syntax) -> Result<(), String> {
+        if let Some((names, sort)) = s.split_once(':') {
+            return self.parse_sort_declaration(names, sort, syntax);
+        }
+        let Some((f, n)) = s.rsplit_once('/') else { return Err(format!("expected a declaration like f/2, found \"{s}\"")) };
+        let Ok(n) = n.trim().parse() else { return Err(format!("invalid arity \"{n}\"")) };
+        let f = f.trim();
+        if f.is_empty() || syntax.is_var_name(f) {
+            return Err(format!("invalid function symbol \"{f}\""));
+        }
+        self.declare(gsymb_add(f.to_string()), n)
+    }
+
+    fn parse_sort_declaration(&mut self, names: &str, sort: &str, syntax: &Syntax) -> Result<(), String> {
+        let sort_name = |x: &str| {
+            let x = x.trim();
+            if x.is_empty() || !x.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                return Err(format!("invalid sort \"{x}\""));
+            }
+            Ok(gsymb_add(x.to_string()))
+        };
+        let (args, result) = match sort.split_once("->") {
+            Some((args, result)) => (args.split('*').map(sort_name).collect::<Result<Vec<_>, _>>()?, sort_name(result)?),
+            None => (Vec::new(), sort_name(sort)?),
+        };
+
+        for name in names.split(',').map(str::trim) {
+            if name.is_empty() { return Err("expected a symbol before \":\"".to_string()) }
+            let x = gsymb_add(name.to_string());
+            if syntax.is_var_name(name) {
+                if !args.is_empty() { return Err(format!("variable {name} cannot have argument sorts")) }
+                self.declare_var(x, result)?;
+            } else {
+                self.declare_sort(x, args.clone(), result)?;
+            }
+        }
+        Ok(())
+    }
+
+    // the sort of the i-th argument of f, if declared.
+    pub fn arg_sort(&self, f: Symbol, i: usize) -> Option<Symbol> {
+        self.sorts.get(&f).map(|(args, _)| args[i])
+    }
+
+    // the sort of t, if known.
+    pub fn sort_of(&self, t: &Term, vars: &VarSorts) -> Option<Symbol> {
+        match t {
+            Term::Var(x) => vars.get(x).copied(),
+            Term::Fun(f, _) => self.sorts.get(f).map(|(_, result)| *result),
+        }
+    }
+
+    // checks that t is well-sorted, and of the given sort (if any),
+    // inferring the sorts of the variables of t.
+    pub fn infer_sorts(&self, t: &Term, sort: Option<Symbol>, vars: &mut VarSorts) -> Result<(), String> {
+        match t {
+            Term::Var(x) => {
+                let Some(sort) = sort else { return Ok(()) };
+                match vars.get(x) {
+                    Some(s) if *s != sort => Err(format!("{x} is used with the sorts {s} and {sort}")),
+                    _ => {
+                        vars.insert(*x, sort);
+                        Ok(())
+                    },
+                }
+            },
+            Term::Fun(f, args) => {
+                if let Some(sort) = sort && let Some(s) = self.sort_of(t, vars) && s != sort {
+                    return Err(format!("{t} has sort {s}, but is used as {sort}"));
+                }
+                for (i, x) in args.iter().enumerate() {
+                    self.infer_sorts(x, self.arg_sort(*f, i), vars)?;
+                }
+                Ok(())
+            },
+        }
+    }
+
+    // checks that both sides are well-sorted and of the same sort.
+    pub fn infer_equation_sorts(&self, lhs: &Term, rhs: &Term, vars: &mut VarSorts) -> Result<(), String> {
+        self.infer_sorts(lhs, None, vars)?;
+        self.infer_sorts(rhs, self.sort_of(lhs, vars), vars)?;
+        // the lhs may be a variable, whose sort is only known from the rhs.
+        self.infer_sorts(lhs, self.sort_of(rhs, vars), vars)
+    }
+
+    // the sorts of the variables of an equation, as far as they are known.
+    pub fn var_sorts(&self, lhs: &Term, rhs: &Term) -> VarSorts {
+        let mut vars = VarSorts::new();
+        let _ = self.infer_equation_sorts(lhs, rhs, &mut vars);
+        vars
+    }
+
+    // whether the variable x can be bound to t.
+    pub fn fits(&self, x: Symbol, t: &Term, vars: &VarSorts) -> bool {
+        match (vars.get(&x), self.sort_of(t, vars)) {
+            (Some(s1), Some(s2)) => *s1 == s2,
+            _ => true,
+        }
+    }
+
+    // checks the arities of all symbols in t, inferring the arities of unknown symbols.
+    pub fn check(&mut self, t: &Term) -> Result<(), String> {
+        let Term::Fun(f, args) = t else { return Ok(()) };
+        match self.arity(*f) {
+            Some(n) if n != args.len() => {
+                let plural = if args.len() == 1 { "" } else { "s" };
+                return Err(format!("{f} has arity {n}, but is applied to {} argument{plural} in {t}", args.len()));
+            },
+            Some(_) => {},
+            None => { self.arities.insert(*f, args.len()); },
+        }
+        args.iter().try_for_each(|x| self.check(x))
+    }
+
+    // checks the arities, and the sorts with respect to the declared sorts of the variables.
+    pub fn check_equation(&mut self, eq: &Equation) -> Result<(), String> {
+        self.check(&eq.lhs)?;
+        self.check(&eq.rhs)?;
+        let mut vars = self.vars.clone();
+        self.infer_equation_sorts(&eq.lhs, &eq.rhs, &mut vars)
+    }
+
+    // the sorts of the variables of an input equation, including the declared ones.
+    pub fn equation_sorts(&self, eq: &Equation) -> VarSorts {
+        let mut vars = self.vars.clone();
+        let _ = self.infer_equation_sorts(&eq.lhs, &eq.rhs, &mut vars);
+        let eq_vars = get_vars(&eq.lhs).into_keys().chain(get_vars(&eq.rhs).into_keys()).collect::<Vec<_>>();
+        vars.retain(|x, _| eq_vars.contains(x));
+        vars
+    }
+
+    // adds the symbols of the other signature, which have to agree with the known ones.
+    pub fn merge(&mut self, other: &Signature) -> Result<(), String> {
+        other.arities.iter().try_for_each(|(f, n)| self.declare(*f, *n))?;
+        other.sorts.iter().try_for_each(|(f, (args, result))| self.declare_sort(*f, args.clone(), *result))?;
+        other.vars.iter().try_for_each(|(x, sort)| self.declare_var(*x, *sort))
+    }
+}
+
+// the sorts of the renamed variables; variables not renamed are dropped.
+pub fn rename_sorts(vars: &VarSorts, renaming: &Subst) -> VarSorts {
+    vars.iter().filter_map(|(x, sort)| match renaming.get(x) {
+        Some(Term::Var(y)) => Some((*y, *sort)),
+        _ => None,
+    }).collect()
+}
+
+impl Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let decls: Vec<String> = self.arities.iter().map(|(g, n)| match self.sorts.get(g) {
+            Some((args, result)) if args.is_empty() => format!("{g} : {result}"),
+            Some((args, result)) => {
+                let args: Vec<String> = args.iter().map(|x| x.to_string()).collect();
+                format!("{g} : {} -> {result}", args.join(" * "))
+            },
+            None => format!("{g}/{n}"),
+        }).collect();
+        write!(f, "{}", decls.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn arities() {
+        let mut sig = Signature::default();
+        sig.parse_declaration("m/2", &Syntax::default()).unwrap();
+        assert!(sig.parse_declaration("m/1", &Syntax::default()).is_err());
+        assert!(sig.parse_declaration("X/1", &Syntax::default()).is_err());
+
+        sig.check_equation(&Equation::parse("m(n(X), X) = e").unwrap()).unwrap();
+        assert_eq!(sig.to_string(), "e/0, m/2, n/1");
+        let e = sig.check(&Term::parse("n(m(X))").unwrap()).unwrap_err();
+        assert_eq!(e, "m has arity 2, but is applied to 1 argument in m(X)");
+        assert!(sig.check(&Term::parse("e(X)").unwrap()).is_err());
+    }
+
+    #[test]
+    fn sorts() {
+        let mut sig = Signature::default();
+        for d in ["m : G * G -> G", "e : G", "act : G * S -> S", "V : S"] {
+            sig.parse_declaration(d, &Syntax::default()).unwrap();
+        }
+        assert_eq!(sig.to_string(), "act : G * S -> S, e : G, m : G * G -> G");
+        sig.check_equation(&Equation::parse("act(m(X, Y), V) = act(X, act(Y, V))").unwrap()).unwrap();
+        sig.check_equation(&Equation::parse("X = m(X, e)").unwrap()).unwrap();
+        assert!(sig.check_equation(&Equation::parse("act(V, V) = V").unwrap()).is_err());
+        assert!(sig.check_equation(&Equation::parse("act(e, X) = m(X, X)").unwrap()).is_err());
+
+        // the variable Z of sort G cannot be bound to a term of sort S.
+        let (l, r) = (Term::parse("act(X, V)").unwrap(), Term::parse("m(Z, Y)").unwrap());
+        let mut vars = sig.var_sorts(&l, &l);
+        vars.extend(sig.var_sorts(&r, &r));
+        assert!(unify(&l, &Term::Var(gsymb_add("Z".to_string()))).is_some());
+        assert!(unify_sorted(&l, &Term::Var(gsymb_add("Z".to_string())), &sig, &mut vars.clone()).is_none());
+        assert!(pat_match_sorted(&r, &Term::parse("m(e, e)").unwrap(), None, &sig, &vars).is_some());
+        assert!(pat_match_sorted(&Term::Var(gsymb_add("Z".to_string())), &Term::parse("act(e, W)").unwrap(), None, &sig, &vars).is_none());
+    }
+}
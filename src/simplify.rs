@@ -1,60 +1,137 @@
 use crate::*;
 
-pub fn simplify_converge(eq: Equation, state: &State) -> Equation {
-    // bring eq into special d normal form to differentiate it from other rules.
-    let mut eq = canonize_vars_d(eq);
+// a rule, or an equation used in one direction.
+pub struct Directed<'s> {
+    pub id: Id,
+    // whether an equation is used from right to left.
+    pub reversed: bool,
+    pub lhs: &'s Term,
+    pub rhs: &'s Term,
+    pub sorts: &'s VarSorts,
+    // rules can always be applied,
+    // whereas equations are only applied to instances that are ordered from left to right (ordered rewriting).
+    pub oriented: bool,
+}
 
-    loop {
-        let eq2 = simplify(eq.clone(), state);
-        if eq == eq2 { return eq }
-        eq = eq2;
+// all rules and all equations in both directions.
+pub fn directed(state: &State) -> Vec<Directed<'_>> {
+    let mut out = Vec::new();
+    for Rule { lhs, rhs, meta } in &state.rules {
+        out.push(Directed { id: meta.id, reversed: false, lhs, rhs, sorts: &meta.sorts, oriented: true });
+    }
+    for Equation { lhs, rhs, meta } in &state.equations {
+        out.push(Directed { id: meta.id, reversed: false, lhs, rhs, sorts: &meta.sorts, oriented: false });
+        out.push(Directed { id: meta.id, reversed: true, lhs: rhs, rhs: lhs, sorts: &meta.sorts, oriented: false });
     }
+    out
 }
 
-pub fn simplify(mut rw: Equation, state: &State) -> Equation {
-    for rw_@(_, _, ori_) in state {
-        if !ori_ { continue }
-        assert!(v_disjoint(&get_vars_eq(&rw), &get_vars_eq(&rw_)));
+// rewrites both sides of the equation to their normal forms w.r.t. the state.
+// The result is recorded as simplified from eq, with the steps taken.
+pub fn simplify_converge(eq: Equation, state: &State) -> Equation {
+    simplify_sides(eq, state, true)
+}
 
-        let (l, r, ori) = &rw;
+// like simplify_converge, but only the rhs is rewritten.
+pub fn simplify_rhs(eq: Equation, state: &State) -> Equation {
+    simplify_sides(eq, state, false)
+}
 
-        // output:
-        let l2 = if !ori || ruleorder_gt(&rw, &rw_) {
-            simplify_single(l.clone(), &rw_)
-        } else { l.clone() };
+fn simplify_sides(eq: Equation, state: &State, lhs: bool) -> Equation {
+    // bring eq into special d normal form to differentiate it from other rules.
+    let renaming = renaming(&eq, d_name);
+    let l = apply_subst(&eq.lhs, &renaming);
+    let r = apply_subst(&eq.rhs, &renaming);
 
-        let r2 = simplify_single(r.clone(), &rw_);
+    let (lhs, lhs_steps) = if lhs { normalize(l, state) } else { (l, Vec::new()) };
+    let (rhs, rhs_steps) = normalize(r, state);
+    let sorts = rename_sorts(&eq.meta.sorts, &renaming);
+    let origin = Origin::Simplified { from: eq.meta.id, renaming, lhs_steps, rhs_steps };
+    Equation { lhs, rhs, meta: Meta { id: eq.meta.id, origin, sorts } }
+}
 
-        let ori2 = *ori && (*l == l2);
+// applies every rule and equation once, at all positions.
+pub fn simplify(mut term: Term, state: &State, steps: &mut Vec<Rewrite>) -> Term {
+    for d in directed(state) {
+        let order = if d.oriented { None } else { Some(&*state.order) };
+        let sig = &state.signature;
+        let mut vars = d.sorts.clone();
+        if sig.is_sorted() { vars.extend(sig.var_sorts(d.lhs, d.rhs)) }
+        let sorts = sig.is_sorted().then_some((sig, &vars));
+        let sort = sorts.and_then(|(sig, vars)| sig.sort_of(&term, vars));
+        let mut found = Vec::new();
+        term = rewrite_single(term, &d, order, sorts, sort, &mut Vec::new(), &mut found);
+        if found.is_empty() { continue }
 
-        rw = (l2, r2, ori2);
+        // reconstruct the intermediate terms backwards, starting from the result.
+        let mut t = term.clone();
+        let mut new = Vec::new();
+        for (pos, subst) in found.into_iter().rev() {
+            let prev = pos_set(&t, &pos, &apply_subst(d.lhs, &subst));
+            new.push(Rewrite { rule: d.id, reversed: d.reversed, pos, subst, term: t });
+            t = prev;
+        }
+        steps.extend(new.into_iter().rev());
     }
-    rw
+    term
 }
 
-pub fn simplify_single(mut term: Term, eq: &Equation) -> Term {
-    let (_, _, ori) = eq;
-    assert!(ori);
-
-    assert!(v_disjoint(&get_vars(&term), &get_vars_eq(&eq)));
+// rewrites term with the directed fact at every position (top-down), recording the positions and matches.
+// With an order given, only instances with σl > σr are rewritten.
+// With sorts (of the variables of d), only matches of the sort of term are rewritten.
+fn rewrite_single(mut term: Term, d: &Directed, order: Option<&dyn TermOrder>, sorts: Option<(&Signature, &VarSorts)>, sort: Option<Symbol>,
+                  pos: &mut Pos, found: &mut Vec<(Pos, Subst)>) -> Term {
+    assert!(v_disjoint(&get_vars(&term), &get_vars(d.lhs)));
+    assert!(v_disjoint(&get_vars(&term), &get_vars(d.rhs)));
 
     // root level application
-    if let Some(subst) = pat_match(&eq.0, &term) {
-        term = apply_subst(&eq.1, &subst);
+    let subst = match sorts {
+        Some((sig, vars)) => pat_match_sorted(d.lhs, &term, sort, sig, vars),
+        None => pat_match(d.lhs, &term),
+    };
+    if let Some(subst) = subst {
+        let term2 = apply_subst(d.rhs, &subst);
+        if order.is_none_or(|o| o.gt(&term, &term2)) {
+            term = term2;
+            found.push((pos.clone(), subst));
+        }
     }
     match term {
         Term::Fun(f, args) => {
-            let args = args.into_iter().map(|x| simplify_single(x, eq)).collect();
+            let args = args.into_iter().enumerate().map(|(i, x)| {
+                pos.push(i);
+                let sort = sorts.and_then(|(sig, _)| sig.arg_sort(f, i));
+                let x = rewrite_single(x, d, order, sorts, sort, pos, found);
+                pos.pop();
+                x
+            }).collect();
             Term::Fun(f, args)
         }
         term => term,
     }
 }
 
+// whether the lhs of the rule can be simplified by some other rule of the state.
+// rule must not share variables with the rules of the state.
+pub fn collapsible(rule: &Rule, state: &State) -> bool {
+    state.rules.iter().any(|rule_| ruleorder_gt(rule, rule_, &*state.order))
+}
+
+// rewrites term to its normal form w.r.t. the state, while recording every step.
+// term must not share variables with the state.
+pub fn normalize(mut term: Term, state: &State) -> (Term, Vec<Rewrite>) {
+    let mut steps = Vec::new();
+    loop {
+        let n = steps.len();
+        term = simplify(term, state, &mut steps);
+        if steps.len() == n { return (term, steps) }
+    }
+}
+
 // s -> t |> l -> r
-fn ruleorder_gt((s, t, _): &Equation, (l, r, _): &Equation) -> bool {
-    if literally_similar(&s, &l) {
-        gt(t, r)
+fn ruleorder_gt(Rule { lhs: s, rhs: t, .. }: &Rule, Rule { lhs: l, rhs: r, .. }: &Rule, order: &dyn TermOrder) -> bool {
+    if literally_similar(s, l) {
+        order.gt(t, r)
     } else {
         encompassment_gte(s, l)
     }
@@ -70,4 +147,3 @@ fn encompassment_gte(t: &Term, p: &Term) -> bool {
     }
     false
 }
-
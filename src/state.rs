@@ -1,53 +1,528 @@
 use crate::*;
 
-pub type Equation = (/*lhs*/ Term, /*rhs*/ Term, /*is oriented*/ bool);
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-pub type State = Vec<Equation>;
+// The E/R presentation of completion:
+// the unoriented equations E, and the oriented rules R form the active facts,
+// which are interreduced and have all their critical pairs computed.
+// New equations wait in the passive queue until they are selected.
+#[derive(Clone)]
+pub struct State {
+    pub equations: Vec<Equation>,
+    pub rules: Vec<Rule>,
+    pub passive: Passive,
 
-pub fn kbc(mut state: State) -> State {
-    dump_state(&state);
-    for i in 0.. {
-        println!("-------------------");
-        let state2 = nondeduce_step(state.clone());
-        let state2 = deduce_step(state2.clone());
-        if state == state2 { break }
-        state = state2;
-        dump_state(&state);
+    // the reduction order used for orienting equations.
+    pub order: Arc<dyn TermOrder>,
+
+    // the function symbols of the problem.
+    pub signature: Signature,
+    // the operators of the problem, for printing.
+    pub syntax: Syntax,
+
+    // every fact ever added, indexed by id.
+    // A fact whose sides change is recorded again under a new id, so the parents of each fact stay available.
+    history: Vec<Equation>,
+}
+
+impl Default for State {
+    fn default() -> State {
+        State {
+            equations: Vec::new(),
+            rules: Vec::new(),
+            passive: Passive::default(),
+            order: Arc::new(Kbo::default()),
+            signature: Signature::default(),
+            syntax: Syntax::default(),
+            history: Vec::new(),
+        }
+    }
+}
+
+impl State {
+    pub fn new() -> State {
+        State::default()
+    }
+
+    // records the equation in the history under a fresh id.
+    fn record(&mut self, mut eq: Equation) -> Equation {
+        eq.meta.id = self.history.len();
+        self.history.push(eq.clone());
+        eq
+    }
+
+    // the fact new, derived from the recorded fact old (by simplification, renaming or orientation).
+    // Only if its sides differ, it is recorded as a new fact.
+    fn revise(&mut self, mut new: Equation, old: &Equation) -> Equation {
+        if new.sides() == old.sides() { return old.clone() }
+        if let Origin::Simplified { from, .. } = &mut new.meta.origin {
+            *from = old.meta.id;
+        }
+        self.record(new)
+    }
+
+    // adds the equation to the passive queue under a fresh id.
+    pub fn add_equation(&mut self, eq: Equation) {
+        let eq = self.record(eq);
+        self.passive.push(eq);
+    }
+
+    // adds the rule to the active rules under a fresh id.
+    pub fn add_rule(&mut self, rule: Rule) {
+        let rule = self.record(rule.into_equation()).into_rule();
+        self.rules.push(rule);
+    }
+
+    // the fact with the given id, as it was recorded.
+    pub fn fact(&self, id: Id) -> &Equation {
+        &self.history[id]
+    }
+
+    // the ids of all facts the given one is derived from, including itself.
+    // Parents come before their children.
+    pub fn derivation(&self, id: Id) -> Vec<Id> {
+        let mut out = Vec::new();
+        let mut todo = vec![id];
+        while let Some(x) = todo.pop() {
+            if out.contains(&x) { continue }
+            out.push(x);
+            todo.extend(self.fact(x).meta.origin.parents());
+        }
+        // facts are recorded after their parents.
+        out.sort();
+        out
+    }
+
+    // all function symbols occurring in the state, together with their arities.
+    pub fn funs(&self) -> BTreeMap<Symbol, usize> {
+        let mut out = BTreeMap::new();
+        let eqs = self.equations.iter().chain(self.passive.iter()).map(|e| e.sides());
+        for (l, r) in eqs.chain(self.rules.iter().map(|r| r.sides())) {
+            acc_funs(l, &mut out);
+            acc_funs(r, &mut out);
+        }
+        out
+    }
+
+    // whether the active facts contain an equation or rule with exactly these sides.
+    pub fn contains(&self, x: &impl Sides) -> bool {
+        self.equations.iter().any(|e| e.sides() == x.sides())
+            || self.rules.iter().any(|r| r.sides() == x.sides())
+    }
+
+    // a state only containing the active facts, for rewriting.
+    fn active(&self) -> State {
+        State {
+            equations: self.equations.clone(),
+            rules: self.rules.clone(),
+            order: self.order.clone(),
+            signature: self.signature.clone(),
+            syntax: self.syntax.clone(),
+            ..State::default()
+        }
+    }
+
+    // a state only containing the active fact with the given id.
+    fn only(&self, id: Id) -> State {
+        State {
+            equations: self.equations.iter().filter(|e| e.meta.id == id).cloned().collect(),
+            rules: self.rules.iter().filter(|r| r.meta.id == id).cloned().collect(),
+            order: self.order.clone(),
+            signature: self.signature.clone(),
+            syntax: self.syntax.clone(),
+            ..State::default()
+        }
+    }
+}
+
+impl FromIterator<Equation> for State {
+    fn from_iter<I: IntoIterator<Item=Equation>>(it: I) -> State {
+        let mut state = State::new();
+        for eq in it {
+            state.add_equation(eq);
+        }
+        state
     }
-    state
 }
 
-fn orient_one((l, r, ori): Equation) -> Equation {
-    if ori { return (l, r, ori) }
-    if gt(&l, &r) { return (l, r, true) }
-    if gt(&r, &l) { return (r, l, true) }
-    (l, r, ori)
+#[derive(Clone, Default, Debug)]
+pub struct Config {
+    // unfailing (ordered) completion:
+    // unorientable equations take part in critical pairs, instead of being ignored.
+    pub unfailing: bool,
+
+    // how the given equations are selected.
+    pub heuristic: Heuristic,
+
+    // resource limits.
+    pub max_iterations: Option<usize>,
+    pub max_rules: Option<usize>,
+    // equations containing a heavier term (counting symbols) are dropped.
+    pub max_weight: Option<usize>,
+    pub timeout: Option<Duration>,
+}
+
+pub enum Status {
+    // every goal has been shown to hold.
+    Proved,
+
+    // the passive queue ran empty,
+    // hence the state is convergent (or ground convergent, if it still contains equations).
+    Converged,
+
+    // the equation could not be oriented, and unfailing completion was disabled.
+    Failed(Equation),
+
+    // completion was stopped early, the state is not complete.
+    Stopped(Limit),
+}
+
+// the resource limit that stopped completion.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Limit {
+    Iterations,
+    Rules,
+    // some equation had to be dropped.
+    Weight,
+    Time,
+}
+
+pub struct Outcome {
+    pub state: State,
+    pub iterations: usize,
+    pub status: Status,
 }
 
-fn nondeduce_step(state: State) -> State {
-    let mut new_state = Vec::new();
-    for x in &state {
+impl Outcome {
+    // whether goals that were not proven are known not to hold.
+    // That is the case after convergence, unless equations remain and a goal has variables:
+    // then the result is only ground convergent, and ordered rewriting may miss non-ground goals.
+    // Without goals, there is nothing to disprove.
+    pub fn disproves(&self, goals: &[Equation]) -> bool {
+        let ground = |g: &Equation| get_vars(&g.lhs).is_empty() && get_vars(&g.rhs).is_empty();
+        !goals.is_empty() && matches!(self.status, Status::Converged) && (self.state.equations.is_empty() || goals.iter().all(ground))
+    }
+
+    // a one-line summary of the outcome, like "converged after 3 iterations".
+    pub fn summary(&self, goals: &[Equation]) -> String {
+        let n = self.iterations;
+        match &self.status {
+            Status::Proved => format!("proved after {n} iterations"),
+            Status::Converged if self.disproves(goals) => format!("not proved: converged after {n} iterations"),
+            Status::Converged if !goals.is_empty() => format!("not proved: ground convergent after {n} iterations, goals with variables are undecided"),
+            Status::Converged => format!("converged after {n} iterations"),
+            Status::Failed(eq) => format!("failed after {n} iterations: cannot orient {}", self.state.syntax.show(eq)),
+            Status::Stopped(limit) => format!("stopped after {n} iterations: {limit} reached"),
+        }
+    }
+}
+
+// runs completion until it converges, fails or hits a limit; or until all goals are proven (if there are any).
+// Every iteration selects one passive equation as "given" equation, which is simplified and added to the active facts,
+// the other active facts are simplified by it, and its critical pairs with them are added to the passive queue.
+pub fn kbc(mut state: State, goals: &[Equation], config: &Config) -> Outcome {
+    let start = Instant::now();
+    // whether an equation was dropped for being too heavy.
+    let mut dropped = false;
+    let too_heavy = |eq: &Equation| config.max_weight.is_some_and(|n| weight(&eq.lhs).max(weight(&eq.rhs)) > n);
+
+    state.passive.set_heuristic(&config.heuristic, goals);
+    for i in 0.. {
+        if !goals.is_empty() && goals.iter().all(|g| joinable(g, &state)) {
+            return Outcome { state, iterations: i, status: Status::Proved };
+        }
+
+        let limit = if config.max_iterations.is_some_and(|n| i >= n) { Some(Limit::Iterations) }
+            else if config.max_rules.is_some_and(|n| state.rules.len() >= n) { Some(Limit::Rules) }
+            else if config.timeout.is_some_and(|t| start.elapsed() >= t) { Some(Limit::Time) }
+            else { None };
+        if let Some(limit) = limit {
+            return Outcome { state, iterations: i, status: Status::Stopped(limit) };
+        }
+
+        let Some(given) = state.passive.pop() else {
+            let status = if dropped { Status::Stopped(Limit::Weight) } else { Status::Converged };
+            return Outcome { state, iterations: i, status };
+        };
+        let eq = simplify_converge(given.clone(), &state);
+
         // delete
-        if x.0 == x.1 { continue }
+        if eq.lhs == eq.rhs { continue }
+
+        if too_heavy(&eq) {
+            dropped = true;
+            continue
+        }
+
+        let eq = canonize_vars(eq);
+        let id = match orient_one(&eq, &*state.order) {
+            Some(rule) => {
+                let rule = canonize_vars(rule);
+                if state.contains(&rule) { continue }
+                let rule = state.revise(rule.into_equation(), &given).into_rule();
+                let id = rule.meta.id;
+                state.rules.push(rule);
+                id
+            },
+            None if config.unfailing => {
+                if state.contains(&eq) { continue }
+                let eq = state.revise(eq, &given);
+                let id = eq.meta.id;
+                state.equations.push(eq);
+                id
+            },
+            None => {
+                return Outcome { state, iterations: i, status: Status::Failed(eq) };
+            },
+        };
+
+        interreduce(&mut state, id);
+        let mut new: Vec<Equation> = Vec::new();
+        for cp in deduce(id, &state, config.unfailing) {
+            let eq = canonize_vars(simplify_converge(cp.clone(), &state));
+            if eq.lhs == eq.rhs || state.contains(&eq) || new.iter().any(|x| x.sides() == eq.sides()) { continue }
+            if too_heavy(&eq) {
+                dropped = true;
+                continue
+            }
+            let cp = state.record(cp);
+            new.push(state.revise(eq, &cp));
+        }
+        for eq in new {
+            state.passive.push(eq);
+        }
+    }
+    unreachable!()
+}
+
+// both sides of the goal have the same normal form w.r.t. the state.
+pub fn joinable(goal: &Equation, state: &State) -> bool {
+    let eq = simplify_converge(goal.clone(), state);
+    eq.lhs == eq.rhs
+}
+
+// orients the equation into a rule, if possible.
+fn orient_one(eq: &Equation, order: &dyn TermOrder) -> Option<Rule> {
+    if order.gt(&eq.lhs, &eq.rhs) { return Some(eq.clone().into_rule()) }
+    if order.gt(&eq.rhs, &eq.lhs) { return Some(eq.clone().flip().into_rule()) }
+    None
+}
+
+// simplifies the active facts with the new active fact (with the given id).
+// Facts that cannot stay active are moved back to the passive queue.
+fn interreduce(state: &mut State, id: Id) {
+    let old = state.active();
+    let new = old.only(id);
 
-        let x = x.clone();
-        let x = orient_one(x);
-        let x = simplify_converge(x, &state);
-        let x = canonize_vars(x);
-        if !new_state.contains(&x) {
-            new_state.push(x);
+    state.rules.clear();
+    for rule in old.rules.iter() {
+        if rule.meta.id == id {
+            state.rules.push(rule.clone());
+            continue
+        }
+
+        // collapse: rules whose lhs becomes reducible are turned back into equations.
+        if collapsible(&canonize_vars_d(rule.clone()), &new) {
+            state.passive.push(rule.clone().into_equation());
+            continue
+        }
+
+        // compose
+        let eq = rule.clone().into_equation();
+        if canonize_vars(simplify_rhs(eq.clone(), &new)).sides() != eq.sides() {
+            let eq2 = canonize_vars(simplify_rhs(eq.clone(), &old));
+            let rule = state.revise(eq2, &eq).into_rule();
+            state.rules.push(rule);
+        } else {
+            state.rules.push(rule.clone());
         }
     }
 
-    new_state
+    state.equations.clear();
+    for eq in old.equations.iter() {
+        if eq.meta.id == id {
+            state.equations.push(eq.clone());
+            continue
+        }
+
+        let eq2 = simplify_converge(eq.clone(), &new);
+        if canonize_vars(eq2).sides() != eq.sides() {
+            state.passive.push(eq.clone());
+        } else {
+            state.equations.push(eq.clone());
+        }
+    }
 }
 
 pub fn dump_state(state: &State) {
     println!("STATE:");
-    for (l, r, ori) in state {
-        let l = l.to_string();
-        let r = r.to_string();
-        let op = if *ori { "->" } else { "=" };
-        println!("{l} {op} {r}");
+    for rule in &state.rules {
+        println!("{}", state.syntax.show(rule));
+    }
+    for eq in &state.equations {
+        println!("{}", state.syntax.show(eq));
+    }
+}
+
+// prints the derivations of the facts with the given ids, every fact once.
+pub fn dump_derivation(state: &State, ids: &[Id]) {
+    let mut all: Vec<Id> = ids.iter().flat_map(|id| state.derivation(*id)).collect();
+    all.sort();
+    all.dedup();
+
+    println!("DERIVATION:");
+    for id in all {
+        let fact = state.fact(id);
+        println!("{id}: {}    ({})", state.syntax.show(fact), fact.meta.origin);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    fn directed(fact: &Equation, reversed: bool) -> (&Term, &Term) {
+        if reversed { (&fact.rhs, &fact.lhs) } else { (&fact.lhs, &fact.rhs) }
+    }
+
+    // checks that the fact follows from its parents as recorded.
+    fn check(state: &State, id: Id) {
+        let fact = state.fact(id);
+        match &fact.meta.origin {
+            Origin::Input => {},
+            Origin::CriticalPair { inner, inner_reversed, inner_subst, outer, outer_reversed, outer_subst, pos } => {
+                let (l, r) = directed(state.fact(*inner), *inner_reversed);
+                let (s, t) = directed(state.fact(*outer), *outer_reversed);
+                let s = apply_subst(s, outer_subst);
+                assert_eq!(pos_idx(&s, pos), &apply_subst(l, inner_subst));
+                assert_eq!(fact.lhs, pos_set(&s, pos, &apply_subst(r, inner_subst)));
+                assert_eq!(fact.rhs, apply_subst(t, outer_subst));
+            },
+            Origin::Simplified { from, renaming, lhs_steps, rhs_steps } => {
+                let replay = |t: &Term, steps: &[Rewrite]| steps.iter().fold(apply_subst(t, renaming), |t, step| {
+                    let (l, r) = directed(state.fact(step.rule), step.reversed);
+                    assert_eq!(pos_idx(&t, &step.pos), &apply_subst(l, &step.subst));
+                    let t = pos_set(&t, &step.pos, &apply_subst(r, &step.subst));
+                    assert_eq!(t, step.term);
+                    t
+                });
+                let from = state.fact(*from);
+                let (l, r) = (replay(&from.lhs, lhs_steps), replay(&from.rhs, rhs_steps));
+                assert!(fact.sides() == (&l, &r) || fact.sides() == (&r, &l));
+            },
+        }
+    }
+
+    #[test]
+    fn derivations() {
+        let state: State = ["m(e, X) = X", "m(n(X), X) = e", "m(m(X, Y), Z) = m(X, m(Y, Z))"].iter()
+            .map(|e| Equation::parse(e).unwrap())
+            .collect();
+        let config = Config { max_iterations: Some(30), ..Config::default() };
+        let state = kbc(state, &[], &config).state;
+        for rule in &state.rules {
+            assert_eq!(state.fact(rule.meta.id).sides(), rule.sides());
+            let derivation = state.derivation(rule.meta.id);
+            assert!(derivation.iter().any(|id| state.fact(*id).meta.origin == Origin::Input));
+            for id in derivation {
+                check(&state, id);
+            }
+        }
+    }
+
+    #[test]
+    fn group_theory() {
+        let mut problem = Problem::default();
+        problem.read("precedence n > m > e\nweight n=0\nm(e, X) = X\nm(n(X), X) = e\nm(m(X, Y), Z) = m(X, m(Y, Z))\n", "grp").unwrap();
+        let outcome = kbc(problem.state().unwrap(), &[], &problem.config);
+        assert!(matches!(outcome.status, Status::Converged));
+        let mut rules: Vec<String> = outcome.state.rules.iter().map(|r| r.to_string()).collect();
+        rules.sort();
+        assert_eq!(rules, [
+            "m(X, e) -> X", "m(X, m(n(X), Y)) -> Y", "m(X, n(X)) -> e", "m(e, X) -> X", "m(m(X, Y), Z) -> m(X, m(Y, Z))",
+            "m(n(X), X) -> e", "m(n(X), m(X, Y)) -> Y", "n(e) -> e", "n(m(X, Y)) -> m(n(Y), n(X))", "n(n(X)) -> X",
+        ]);
+    }
+
+    fn state(eqs: &[&str]) -> State {
+        eqs.iter().map(|e| Equation::parse(e).unwrap()).collect()
+    }
+
+    #[test]
+    fn unfailing_commutativity() {
+        let config = Config { unfailing: true, ..Config::default() };
+        let outcome = kbc(state(&["f(X, Y) = f(Y, X)"]), &[], &config);
+        assert!(matches!(outcome.status, Status::Converged));
+        assert_eq!(outcome.state.equations.len(), 1);
+    }
+
+    #[test]
+    fn summaries() {
+        let outcome = kbc(state(&["f(X) = X"]), &[], &Config::default());
+        assert_eq!(outcome.summary(&[]), "converged after 1 iterations");
+
+        let config = Config { unfailing: true, ..Config::default() };
+        let outcome = kbc(state(&["f(X, Y) = f(Y, X)"]), &[], &config);
+        assert_eq!(outcome.summary(&[]), "converged after 1 iterations");
+        assert_eq!(outcome.summary(&[Equation::parse("f(a, b) = a").unwrap()]), "not proved: converged after 1 iterations");
+        let goal = Equation::parse("f(X, a) = X").unwrap();
+        assert!(outcome.summary(&[goal]).starts_with("not proved: ground convergent"));
+    }
+
+    // "c = X" makes all terms equal, but with sorts only those of sort G:
+    // its variable cannot be bound to the terms s and t of sort S, not even once derived as X = Y.
+    #[test]
+    fn sorted_overlaps() {
+        let run = |decls: &str| {
+            let mut problem = Problem::default();
+            problem.read(&format!("{decls}unfailing\nc = X\ng(s) = t\ngoal s = t\n"), "p").unwrap();
+            problem.check().unwrap();
+            kbc(problem.state().unwrap(), &problem.goal_equations(), &problem.config)
+        };
+        assert!(matches!(run("").status, Status::Proved));
+
+        let outcome = run("declare c : G\ndeclare s, t : S\ndeclare g : S -> S\n");
+        assert!(matches!(outcome.status, Status::Converged));
+        let state = &outcome.state;
+        let facts = state.equations.iter().chain(state.passive.iter()).map(|e| (e.sides(), &e.meta.sorts));
+        for ((l, r), sorts) in facts.chain(state.rules.iter().map(|r| (r.sides(), &r.meta.sorts))) {
+            let mut vars = sorts.clone();
+            assert!(state.signature.infer_equation_sorts(l, r, &mut vars).is_ok(), "{l} = {r}");
+            assert!(get_vars(l).keys().chain(get_vars(r).keys()).all(|x| vars.contains_key(x)), "{l} = {r}");
+        }
+    }
+
+    const GROUP: &[&str] = &["m(e, X) = X", "m(n(X), X) = e", "m(m(X, Y), Z) = m(X, m(Y, Z))"];
+
+    #[test]
+    fn outcomes() {
+        let outcome = kbc(state(&["f(X, Y) = f(Y, X)"]), &[], &Config::default());
+        let Status::Failed(eq) = outcome.status else { panic!("commutativity cannot be oriented") };
+        assert_eq!(eq.to_string(), "f(X, Y) = f(Y, X)");
+
+        let config = Config { max_iterations: Some(3), ..Config::default() };
+        let outcome = kbc(state(GROUP), &[], &config);
+        assert!(matches!(outcome.status, Status::Stopped(Limit::Iterations)));
+        assert_eq!(outcome.iterations, 3);
+    }
+
+    #[test]
+    fn limits() {
+        let config = Config { max_rules: Some(2), ..Config::default() };
+        let outcome = kbc(state(GROUP), &[], &config);
+        assert!(matches!(outcome.status, Status::Stopped(Limit::Rules)));
+        assert_eq!(outcome.state.rules.len(), 2);
+
+        // the critical pairs of associativity are heavier, so the passive queue runs empty with some dropped.
+        let config = Config { max_weight: Some(5), ..Config::default() };
+        let outcome = kbc(state(GROUP), &[], &config);
+        assert!(matches!(outcome.status, Status::Stopped(Limit::Weight)));
+        assert!(outcome.state.passive.is_empty());
+
+        let config = Config { timeout: Some(std::time::Duration::ZERO), ..Config::default() };
+        let outcome = kbc(state(GROUP), &[], &config);
+        assert!(matches!(outcome.status, Status::Stopped(Limit::Time)));
+        assert_eq!(outcome.iterations, 0);
     }
 }
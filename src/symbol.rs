@@ -34,7 +34,7 @@ impl SymbolMap {
 
     fn add(&mut self, x: String) -> Symbol {
         if let Some(y) = self.string_to_id.get(&x) {
-            return *y;
+            *y
         } else {
             let i = self.string_to_id.len();
             self.string_to_id.insert(x.clone(), Symbol(i));
@@ -53,6 +53,12 @@ use std::cmp::*;
 
 impl PartialOrd for Symbol {
     fn partial_cmp(&self, other: &Symbol) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Symbol {
+    fn cmp(&self, other: &Symbol) -> Ordering {
         let a = self;
         let b = other;
 
@@ -61,16 +67,9 @@ impl PartialOrd for Symbol {
 
         for (ca, cb) in a.chars().zip(b.chars()) {
             let o = ca.cmp(&cb);
-            if o != Ordering::Equal { return Some(o); }
+            if o != Ordering::Equal { return o; }
         }
 
-        let o = a.len().cmp(&b.len());
-        Some(o)
-    }
-}
-
-impl Ord for Symbol {
-    fn cmp(&self, other: &Symbol) -> Ordering {
-        self.partial_cmp(other).unwrap()
+        a.len().cmp(&b.len())
     }
 }
@@ -0,0 +1,307 @@
+use crate::*;
+
+use std::path::Path;
+
+// A reader for unit equality problems in the TPTP syntax, e.g.
+//   cnf(left_identity, axiom, m(e, X) = X).
+//   cnf(prove_it, negated_conjecture, m(a, b) != m(b, a)).
+//   fof(commutes, conjecture, ! [X, Y] : m(X, Y) = m(Y, X)).
+// Axioms (and other assumed roles) become axioms of the problem,
+// a conjecture or the negation of a negated conjecture becomes a goal.
+
+#[derive(PartialEq, Clone, Debug)]
+enum Tok {
+    // lower words, quoted atoms, numbers and $words.
+    Atom(String),
+    Var(String),
+    Punct(&'static str),
+}
+
+const PUNCT: &[&str] = &["<=>", "<~>", "=>", "<=", "!=", "~|", "~&", "(", ")", "[", "]", ",", ".", ":", "=", "~", "!", "?", "|", "&"];
+
+// the tokens with their line numbers.
+fn tokenize(src: &str) -> Result<Vec<(Tok, usize)>, String> {
+    let mut out = Vec::new();
+    let mut line = 1;
+    let mut rest = src;
+    while let Some(c) = rest.chars().next() {
+        if c == '\n' { line += 1; }
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+        } else if c == '%' {
+            rest = rest.find('\n').map_or("", |i| &rest[i..]);
+        } else if let Some(r) = rest.strip_prefix("/*") {
+            let Some(i) = r.find("*/") else { return Err(format!("{line}: unterminated comment")) };
+            line += r[..i].matches('\n').count();
+            rest = &r[i+2..];
+        } else if c == '\'' || c == '"' {
+            let Some(i) = rest[1..].find(c) else { return Err(format!("{line}: unterminated quote")) };
+            let word = &rest[..i+2];
+            // 'abc' is the same atom as abc.
+            let plain = word.len() > 2 && word[1..].starts_with(|c: char| c.is_ascii_lowercase())
+                && word[1..word.len()-1].chars().all(|c| c.is_alphanumeric() || c == '_');
+            let atom = if c == '\'' && plain { &word[1..word.len()-1] } else { word };
+            out.push((Tok::Atom(atom.to_string()), line));
+            rest = &rest[i+2..];
+        } else if c.is_alphanumeric() || c == '_' || c == '$' {
+            let n = rest[1..].find(|c: char| !(c.is_alphanumeric() || c == '_')).map_or(rest.len(), |i| i+1);
+            let word = rest[..n].to_string();
+            let tok = if c.is_ascii_uppercase() || c == '_' { Tok::Var(word) } else { Tok::Atom(word) };
+            out.push((tok, line));
+            rest = &rest[n..];
+        } else {
+            let Some(p) = PUNCT.iter().find(|p| rest.starts_with(**p)) else {
+                return Err(format!("{line}: unexpected character '{c}'"));
+            };
+            out.push((Tok::Punct(p), line));
+            rest = &rest[p.len()..];
+        }
+    }
+    Ok(out)
+}
+
+struct Parser {
+    toks: Vec<(Tok, usize)>,
+    i: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Tok> {
+        self.toks.get(self.i).map(|(t, _)| t)
+    }
+
+    fn line(&self) -> usize {
+        self.toks.get(self.i).or(self.toks.last()).map_or(1, |(_, l)| *l)
+    }
+
+    fn error<T>(&self, msg: impl std::fmt::Display) -> Result<T, String> {
+        Err(format!("{}: {msg}", self.line()))
+    }
+
+    fn next(&mut self) -> Result<Tok, String> {
+        let Some((t, _)) = self.toks.get(self.i) else { return self.error("unexpected end of input") };
+        self.i += 1;
+        Ok(t.clone())
+    }
+
+    fn eat(&mut self, p: &str) -> bool {
+        let found = matches!(self.peek(), Some(Tok::Punct(x)) if *x == p);
+        if found { self.i += 1; }
+        found
+    }
+
+    fn expect(&mut self, p: &str) -> Result<(), String> {
+        if self.eat(p) { Ok(()) } else { self.error(format!("expected '{p}'")) }
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        match self.next()? {
+            Tok::Atom(a) => Ok(a),
+            t => { self.i -= 1; self.error(format!("expected a name, found {t:?}")) },
+        }
+    }
+
+    // skips a general term of the annotations.
+    fn skip_general(&mut self) -> Result<(), String> {
+        let mut depth = 0;
+        loop {
+            match self.peek() {
+                None => return self.error("unexpected end of input"),
+                Some(Tok::Punct("(" | "[")) => depth += 1,
+                Some(Tok::Punct(")" | "]")) if depth == 0 => return Ok(()),
+                Some(Tok::Punct(")" | "]")) => depth -= 1,
+                Some(Tok::Punct(",")) if depth == 0 => return Ok(()),
+                _ => {},
+            }
+            self.i += 1;
+        }
+    }
+
+    fn term(&mut self) -> Result<Term, String> {
+        match self.next()? {
+            Tok::Var(x) => Ok(Term::Var(gsymb_add(x))),
+            Tok::Atom(f) => {
+                let f = gsymb_add(f);
+                let mut args = Vec::new();
+                if self.eat("(") {
+                    loop {
+                        args.push(self.term()?);
+                        if !self.eat(",") { break }
+                    }
+                    self.expect(")")?;
+                }
+                Ok(Term::Fun(f, args.into_boxed_slice()))
+            },
+            t => { self.i -= 1; self.error(format!("expected a term, found {t:?}")) },
+        }
+    }
+
+    // a (possibly negated, quantified or parenthesized) equation; returns whether it is positive,
+    // and whether its variables are quantified under an odd number of negations (if they are quantified).
+    fn literal(&mut self, fof: bool) -> Result<(Equation, bool, Option<bool>), String> {
+        if self.eat("(") {
+            let lit = self.literal(fof)?;
+            self.expect(")")?;
+            return Ok(lit);
+        }
+        if self.eat("~") {
+            let (eq, positive, odd) = self.literal(fof)?;
+            return Ok((eq, !positive, odd.map(|x| !x)));
+        }
+        if fof && self.eat("!") {
+            self.expect("[")?;
+            loop {
+                let Tok::Var(_) = self.next()? else { return self.error("expected a variable") };
+                if !self.eat(",") { break }
+            }
+            self.expect("]")?;
+            self.expect(":")?;
+            let (eq, positive, _) = self.literal(fof)?;
+            return Ok((eq, positive, Some(false)));
+        }
+        if self.peek() == Some(&Tok::Punct("?")) {
+            return self.error("existential quantification is not supported");
+        }
+
+        let lhs = self.term()?;
+        let positive = if self.eat("=") { true }
+            else if self.eat("!=") { false }
+            else { return self.error("expected '=' or '!='; only unit equalities are supported") };
+        let rhs = self.term()?;
+        Ok((Equation::new(lhs, rhs), positive, None))
+    }
+}
+
+// parses the TPTP problem, loading included files with the given function.
+pub fn parse_tptp(src: &str, include: &mut dyn FnMut(&str) -> Result<Problem, String>) -> Result<Problem, String> {
+    let mut p = Parser { toks: tokenize(src)?, i: 0 };
+    let mut problem = Problem::default();
+    while p.peek().is_some() {
+        let lang = p.name()?;
+        if lang == "include" {
+            p.expect("(")?;
+            let file = p.name()?;
+            // only the formulas named in the selection list are included, if there is one.
+            let mut selection = None;
+            if p.eat(",") {
+                p.expect("[")?;
+                let mut names = Vec::new();
+                while !p.eat("]") {
+                    names.push(p.name()?);
+                    if !p.eat(",") { p.expect("]")?; break }
+                }
+                selection = Some(names);
+            }
+            p.expect(")")?;
+            p.expect(".")?;
+            let mut inner = include(file.trim_matches('\''))?;
+            if let Some(names) = selection {
+                let known = |n: &String| inner.axioms.iter().chain(&inner.goals).any(|(m, _)| m == n);
+                if let Some(n) = names.iter().find(|n| !known(n)) {
+                    return Err(format!("{file}: no formula named {n}"));
+                }
+                inner.axioms.retain(|(n, _)| names.contains(n));
+                inner.goals.retain(|(n, _)| names.contains(n));
+            }
+            problem.extend(inner).map_err(|e| format!("{file}: {e}"))?;
+            continue
+        }
+        let fof = match &*lang {
+            "cnf" => false,
+            "fof" => true,
+            _ => return p.error(format!("unsupported formula type {lang}")),
+        };
+
+        p.expect("(")?;
+        let name = match p.next()? {
+            Tok::Atom(a) => a,
+            t => return p.error(format!("expected a formula name, found {t:?}")),
+        };
+        p.expect(",")?;
+        let role = p.name()?;
+        p.expect(",")?;
+        let line = p.line();
+        let (eq, positive, odd) = p.literal(fof)?;
+        while p.eat(",") { p.skip_general()?; }
+        p.expect(")")?;
+        p.expect(".")?;
+
+        // goals can only be proven for all instances of their variables, and axioms must hold for all instances.
+        let vars = !get_vars(&eq.lhs).is_empty() || !get_vars(&eq.rhs).is_empty();
+        let existential = odd == Some(true);
+        let negated = role == "negated_conjecture" && !positive;
+        if vars && existential != negated {
+            return Err(format!("{line}: {name} has existentially quantified variables, which are not supported"));
+        }
+        match (&*role, positive) {
+            ("conjecture", true) => problem.goals.push((name, eq)),
+            ("negated_conjecture", false) => problem.goals.push((name, eq)),
+            ("axiom" | "hypothesis" | "definition" | "assumption" | "lemma" | "theorem" | "negated_conjecture", true) => {
+                problem.axioms.push((name, eq))
+            },
+            (_, false) => return Err(format!("{line}: {name} is not a unit equality")),
+            _ => return Err(format!("{line}: unsupported role {role}")),
+        }
+    }
+    problem.check()?;
+    Ok(problem)
+}
+
+// reads a TPTP file.
+// Included files are searched relative to the file, and to the directory given by the TPTP environment variable.
+pub fn read_tptp(path: &str) -> Result<Problem, String> {
+    let src = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+    let dir = Path::new(path).parent().unwrap_or(Path::new(""));
+    let mut include = |file: &str| {
+        let mut candidates = vec![dir.join(file)];
+        if let Ok(root) = std::env::var("TPTP") {
+            candidates.push(Path::new(&root).join(file));
+        }
+        let Some(found) = candidates.iter().find(|c| c.exists()) else {
+            return Err(format!("{path}: cannot find included file {file}"));
+        };
+        read_tptp(&found.to_string_lossy())
+    };
+    parse_tptp(&src, &mut include).map_err(|e| if e.starts_with(path) { e } else { format!("{path}:{e}") })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn tptp_cnf_fof() {
+        let src = "
+            % group theory
+            cnf(left_identity, axiom, m(e, X) = X).
+            cnf(left_inverse, axiom, m(n(X), X) = e).
+            cnf(associativity, axiom, m(m(X, Y), Z) = m(X, m(Y, Z)) ).
+            cnf(prove, negated_conjecture, m(a, n(a)) != e).
+            fof(right_identity, conjecture, ! [X] : (m(X, e) = X), file('x', y)).
+        ";
+        let problem = parse_tptp(src, &mut |_| Ok(Problem::default())).unwrap();
+        assert_eq!(problem.axioms.len(), 3);
+        assert_eq!(problem.axioms[0].0, "left_identity");
+        assert_eq!(problem.goals.len(), 2);
+        assert_eq!(problem.goals[0].1, Equation::parse("m(a, n(a)) = e").unwrap());
+        assert_eq!(problem.goals[1].1, Equation::parse("m(X, e) = X").unwrap());
+
+        assert!(parse_tptp("cnf(a, axiom, f(X) = X | g(X) = X).", &mut |_| Ok(Problem::default())).is_err());
+        assert!(parse_tptp("cnf(a, negated_conjecture, f(X) != X).", &mut |_| Ok(Problem::default())).is_err());
+        let negated = parse_tptp("fof(a, negated_conjecture, ~ (! [X] : f(X) = X)).", &mut |_| Ok(Problem::default())).unwrap();
+        assert_eq!(negated.goals.len(), 1);
+        let e = parse_tptp("cnf(a, axiom, f(X) = X).\ncnf(b, axiom, f(X, Y) = X).", &mut |_| Ok(Problem::default())).unwrap_err();
+        assert_eq!(e, "b: f has arity 1, but is applied to 2 arguments in f(X, Y)");
+    }
+
+    #[test]
+    fn selective_include() {
+        let mut include = |_: &str| parse_tptp("cnf(a, axiom, f(X) = X).\ncnf(b, axiom, g(X) = X).\ncnf(c, axiom, h(X) = X).", &mut |_| Ok(Problem::default()));
+        let problem = parse_tptp("include('Axioms/x.ax', [a, c]).", &mut include).unwrap();
+        let names: Vec<&str> = problem.axioms.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, ["a", "c"]);
+        assert_eq!(parse_tptp("include('Axioms/x.ax').", &mut include).unwrap().axioms.len(), 3);
+        let e = parse_tptp("include('Axioms/x.ax', [a, d]).", &mut include).unwrap_err();
+        assert_eq!(e, "'Axioms/x.ax': no formula named d");
+    }
+}
@@ -0,0 +1,227 @@
+use crate::*;
+
+// The TRS format of the termination and confluence competitions (TPDB, COPS), e.g.
+//   (VAR x y)
+//   (RULES
+//     f(x, e) -> x
+//     g(x, y) -> f(y, x)
+//   )
+// Identifiers are variables only if they are declared in the VAR section.
+
+#[derive(PartialEq, Clone, Debug)]
+enum Tok {
+    LParen, RParen, Comma, Arrow, Equals,
+    Ident(String),
+}
+
+fn tokenize(src: &str) -> Vec<Tok> {
+    let mut out = Vec::new();
+    let mut rest = src;
+    while let Some(c) = rest.chars().next() {
+        let tok = if c.is_whitespace() { None }
+            else if c == '(' { Some(Tok::LParen) }
+            else if c == ')' { Some(Tok::RParen) }
+            else if c == ',' { Some(Tok::Comma) }
+            else if rest.starts_with("->") { Some(Tok::Arrow) }
+            else if rest.starts_with("==") { Some(Tok::Equals) }
+            else {
+                let delimiter = |(i, c): &(usize, char)| {
+                    c.is_whitespace() || "(),".contains(*c) || rest[*i..].starts_with("->") || rest[*i..].starts_with("==")
+                };
+                let n = rest.char_indices().find(delimiter).map_or(rest.len(), |(i, _)| i);
+                out.push(Tok::Ident(rest[..n].to_string()));
+                rest = &rest[n..];
+                continue
+            };
+        let n = match tok { Some(Tok::Arrow | Tok::Equals) => 2, _ => c.len_utf8() };
+        out.extend(tok);
+        rest = &rest[n..];
+    }
+    out
+}
+
+struct Parser {
+    toks: Vec<Tok>,
+    i: usize,
+    vars: Vec<String>,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Tok> {
+        self.toks.get(self.i)
+    }
+
+    fn next(&mut self) -> Result<Tok, String> {
+        let Some(t) = self.toks.get(self.i) else { return Err("unexpected end of input".to_string()) };
+        self.i += 1;
+        Ok(t.clone())
+    }
+
+    fn expect(&mut self, tok: Tok) -> Result<(), String> {
+        let t = self.next()?;
+        if t == tok { Ok(()) } else { Err(format!("expected {tok:?}, found {t:?}")) }
+    }
+
+    fn term(&mut self) -> Result<Term, String> {
+        let Tok::Ident(x) = self.next()? else { return Err("expected an identifier".to_string()) };
+        if self.vars.contains(&x) {
+            return Ok(Term::Var(gsymb_add(x)));
+        }
+
+        let mut args = Vec::new();
+        if self.peek() == Some(&Tok::LParen) {
+            self.i += 1;
+            if self.peek() == Some(&Tok::RParen) {
+                self.i += 1;
+            } else {
+                loop {
+                    args.push(self.term()?);
+                    if self.peek() != Some(&Tok::Comma) { break }
+                    self.i += 1;
+                }
+                self.expect(Tok::RParen)?;
+            }
+        }
+        Ok(Term::Fun(gsymb_add(x), args.into_boxed_slice()))
+    }
+
+    // skips the rest of a section, up to and including its closing paren.
+    fn skip_section(&mut self) -> Result<(), String> {
+        let mut depth = 0;
+        loop {
+            match self.next()? {
+                Tok::LParen => depth += 1,
+                Tok::RParen if depth == 0 => return Ok(()),
+                Tok::RParen => depth -= 1,
+                _ => {},
+            }
+        }
+    }
+}
+
+// parses a TRS; its rules (and equations) become the axioms of the problem.
+pub fn parse_trs(src: &str) -> Result<Problem, String> {
+    let mut p = Parser { toks: tokenize(src), i: 0, vars: Vec::new() };
+    let mut problem = Problem::default();
+    while p.peek().is_some() {
+        p.expect(Tok::LParen)?;
+        let Tok::Ident(section) = p.next()? else { return Err("expected a section name".to_string()) };
+        match &*section {
+            "VAR" => {
+                while let Some(Tok::Ident(x)) = p.peek() {
+                    p.vars.push(x.clone());
+                    p.i += 1;
+                }
+                p.expect(Tok::RParen)?;
+            },
+            "RULES" => {
+                while p.peek() != Some(&Tok::RParen) {
+                    let lhs = p.term()?;
+                    match p.next()? {
+                        Tok::Arrow | Tok::Equals => {},
+                        t => return Err(format!("expected -> after {lhs}, found {t:?}")),
+                    }
+                    let rhs = p.term()?;
+                    if p.peek() == Some(&Tok::Ident("|".to_string())) {
+                        return Err("conditional rules are not supported".to_string());
+                    }
+                    problem.axioms.push((format!("r{}", problem.axioms.len()+1), Equation::new(lhs, rhs)));
+                }
+                p.expect(Tok::RParen)?;
+            },
+            "COMMENT" | "STRATEGY" => p.skip_section()?,
+            _ => return Err(format!("unsupported section {section}")),
+        }
+    }
+    problem.check()?;
+    Ok(problem)
+}
+
+// the rules of the state in the TRS format.
+// Equations cannot be expressed in it, so they are only listed in a comment.
+// The format has no quoting, so names that would not be read back as one identifier are an error.
+pub fn write_trs(state: &State) -> Result<String, String> {
+    let mut vars = BTreeMap::new();
+    for (l, r) in state.rules.iter().map(|r| r.sides()).chain(state.equations.iter().map(|e| e.sides())) {
+        vars.extend(get_vars(l));
+        vars.extend(get_vars(r));
+    }
+
+    let mut out = String::new();
+    let vars = vars.keys().map(|x| trs_name(&x.to_string())).collect::<Result<Vec<String>, String>>()?;
+    out += &format!("(VAR {})\n", vars.join(" "));
+    out += "(RULES\n";
+    for rule in &state.rules {
+        out += &format!("  {} -> {}\n", trs_term(&rule.lhs)?, trs_term(&rule.rhs)?);
+    }
+    out += ")\n";
+    if !state.equations.is_empty() {
+        out += "(COMMENT unorientable equations:\n";
+        for eq in &state.equations {
+            out += &format!("  {} == {}\n", trs_term(&eq.lhs)?, trs_term(&eq.rhs)?);
+        }
+        out += ")\n";
+    }
+    Ok(out)
+}
+
+fn trs_name(name: &str) -> Result<String, String> {
+    let tokens = tokenize(name);
+    if tokens != [Tok::Ident(name.to_string())] {
+        return Err(format!("{name} cannot be written in the TRS format"));
+    }
+    Ok(name.to_string())
+}
+
+fn trs_term(t: &Term) -> Result<String, String> {
+    match t {
+        Term::Var(x) => trs_name(&x.to_string()),
+        Term::Fun(f, args) if args.is_empty() => trs_name(&f.to_string()),
+        Term::Fun(f, args) => {
+            let args = args.iter().map(trs_term).collect::<Result<Vec<String>, String>>()?;
+            Ok(format!("{}({})", trs_name(&f.to_string())?, args.join(",")))
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn trs_roundtrip() {
+        let src = "(VAR x y)\n(COMMENT a (nested) comment)\n(RULES\n  +(x, 0) -> x\n  +(x,s(y))->s(+(x,y))\n)\n";
+        let problem = parse_trs(src).unwrap();
+        assert_eq!(problem.axioms.len(), 2);
+        assert!(matches!(problem.axioms[0].1.lhs.clone(), Term::Fun(_, args) if matches!(args[0], Term::Var(_))));
+
+        let mut state = State::new();
+        for (_, eq) in &problem.axioms {
+            state.add_rule(eq.clone().into_rule());
+        }
+        let out = write_trs(&state).unwrap();
+        assert_eq!(out, "(VAR x y)\n(RULES\n  +(x,0) -> x\n  +(x,s(y)) -> s(+(x,y))\n)\n");
+        assert_eq!(parse_trs(&out).unwrap().axioms, problem.axioms);
+    }
+
+    #[test]
+    fn trs_names() {
+        // names with delimiters cannot be read back, so they are not written.
+        let mut state = State::new();
+        state.add_rule(Equation::parse("c = 'a b'").unwrap().into_rule());
+        assert_eq!(write_trs(&state).unwrap_err(), "'a b' cannot be written in the TRS format");
+        for name in ["'f(x)'", "'a,b'", "'a->b'"] {
+            let mut state = State::new();
+            state.add_rule(Equation::parse(&format!("{name} = c")).unwrap().into_rule());
+            assert!(write_trs(&state).is_err(), "{name}");
+        }
+
+        // other special characters are fine.
+        let mut state = State::new();
+        state.add_rule(Equation::parse("'f*'(X, 'a.b') = X").unwrap().into_rule());
+        let out = write_trs(&state).unwrap();
+        assert_eq!(out, "(VAR X)\n(RULES\n  'f*'(X,'a.b') -> X\n)\n");
+        let axioms = parse_trs(&out).unwrap().axioms;
+        assert_eq!(axioms[0].1.to_string(), "'f*'(X, 'a.b') = X");
+    }
+}
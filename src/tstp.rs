@@ -0,0 +1,145 @@
+use crate::*;
+
+// Output in the conventions of the TPTP world:
+// SZS status lines, and derivations in the TSTP format, so that results can be processed by the usual tooling.
+
+// the SZS status of the outcome; with goals, it states whether the goals are theorems of the axioms.
+pub fn szs_status(outcome: &Outcome, goals: &[Equation]) -> &'static str {
+    match &outcome.status {
+        Status::Proved => "Theorem",
+        Status::Converged if goals.is_empty() => "Satisfiable",
+        Status::Converged if outcome.disproves(goals) => "CounterSatisfiable",
+        Status::Converged => "GaveUp",
+        Status::Failed(_) => "GaveUp",
+        Status::Stopped(Limit::Time) => "Timeout",
+        Status::Stopped(_) => "GaveUp",
+    }
+}
+
+// prints the TSTP derivation of the proven goals of the problem:
+// every fact involved is listed with the inference deriving it, and the names of its parents.
+// The axioms of the problem are expected to have the ids of their index.
+pub fn dump_tstp(name: &str, problem: &Problem, state: &State) {
+    println!("% SZS output start Proof for {name}");
+
+    let mut joins = Vec::new();
+    for (goal_name, goal) in &problem.goals {
+        let (eq, _) = canonize_vars_d_rev(goal.clone());
+        let (s, s_steps) = normalize(eq.lhs, state);
+        let (t, t_steps) = normalize(eq.rhs, state);
+        if s != t { continue }
+        let mut used: Vec<Id> = s_steps.iter().chain(&t_steps).map(|step| step.rule).collect();
+        used.sort();
+        used.dedup();
+        joins.push((goal_name, goal, used));
+    }
+
+    let mut all: Vec<Id> = joins.iter().flat_map(|(_, _, used)| used).flat_map(|id| state.derivation(*id)).collect();
+    all.sort();
+    all.dedup();
+
+    let fact_name = |id: Id| match state.fact(id).meta.origin {
+        Origin::Input => tptp_name(&problem.axioms[id].0),
+        _ => format!("c_{id}"),
+    };
+    let names = |ids: &[Id]| ids.iter().map(|id| fact_name(*id)).collect::<Vec<_>>().join(", ");
+
+    for id in all {
+        let fact = &tptp_vars(state.fact(id));
+        let formula = format!("{} = {}", tptp_term(&fact.lhs), tptp_term(&fact.rhs));
+        let origin = &fact.meta.origin;
+        let inference = match origin {
+            Origin::Input => {
+                println!("cnf({}, axiom, {formula}).", fact_name(id));
+                continue
+            },
+            Origin::CriticalPair { .. } => "superposition",
+            Origin::Simplified { lhs_steps, rhs_steps, .. } if lhs_steps.is_empty() && rhs_steps.is_empty() => "renaming",
+            Origin::Simplified { .. } => "rewriting",
+        };
+        println!("cnf({}, plain, {formula}, inference({inference}, [status(thm)], [{}])).", fact_name(id), names(&origin.parents()));
+    }
+
+    for (goal_name, goal, used) in joins {
+        let goal = tptp_vars(goal);
+        let mut vars = get_vars(&goal.lhs);
+        vars.extend(get_vars(&goal.rhs));
+        let vars: Vec<String> = vars.keys().map(|x| x.to_string()).collect();
+        let quantifier = if vars.is_empty() { String::new() } else { format!("! [{}] : ", vars.join(", ")) };
+        println!("fof({}, theorem, {quantifier}({} = {}), inference(rewriting, [status(thm)], [{}])).",
+            tptp_name(goal_name), tptp_term(&goal.lhs), tptp_term(&goal.rhs), names(&used));
+    }
+
+    println!("% SZS output end Proof for {name}");
+}
+
+// the equation with its variables renamed to TPTP variables, i.e. to upper words:
+// variables like "x", "?x" or "_x" become fresh ones like "X1".
+fn tptp_vars(eq: &Equation) -> Equation {
+    let upper_word = |s: &str| s.starts_with(|c: char| c.is_ascii_uppercase()) && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    let mut vars = get_vars(&eq.lhs);
+    vars.extend(get_vars(&eq.rhs));
+    let names: Vec<String> = vars.keys().map(|x| x.to_string()).collect();
+
+    let mut renaming = Subst::new();
+    let mut n = 0;
+    for (x, name) in vars.keys().zip(&names) {
+        if upper_word(name) { continue }
+        let fresh = loop {
+            n += 1;
+            let fresh = format!("X{n}");
+            if !names.contains(&fresh) { break fresh }
+        };
+        renaming.insert(*x, Term::var(fresh));
+    }
+    eq.clone().rename(&renaming)
+}
+
+// the term in TPTP syntax, i.e. in prefix notation with quoted symbols, where necessary.
+fn tptp_term(t: &Term) -> String {
+    match t {
+        Term::Var(x) => x.to_string(),
+        Term::Fun(f, args) if args.is_empty() => tptp_name(&f.to_string()),
+        Term::Fun(f, args) => {
+            let args: Vec<String> = args.iter().map(tptp_term).collect();
+            format!("{}({})", tptp_name(&f.to_string()), args.join(","))
+        },
+    }
+}
+
+// the name as TPTP atom, quoted unless it is a lower word (or already quoted).
+fn tptp_name(s: &str) -> String {
+    let lower_word = s.starts_with(|c: char| c.is_ascii_lowercase()) && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    let quoted = s.len() > 1 && s.starts_with('\'') && s.ends_with('\'');
+    if lower_word || quoted {
+        s.to_string()
+    } else {
+        format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn tptp_names() {
+        assert_eq!(super::tptp_name("left_identity"), "left_identity");
+        assert_eq!(super::tptp_name("grp.txt:3"), "'grp.txt:3'");
+        let t = Term::parse("f(X, g(a))").unwrap();
+        assert_eq!(super::tptp_term(&t), "f(X,g(a))");
+        let eq = super::tptp_vars(&Equation::parse("f(?x, X1, _z) = g(?x)").unwrap());
+        assert_eq!(format!("{} = {}", super::tptp_term(&eq.lhs), super::tptp_term(&eq.rhs)), "f(X2,X1,X3) = g(X2)");
+    }
+
+    #[test]
+    fn ground_convergence() {
+        let state: State = [Equation::parse("f(X, Y) = f(Y, X)").unwrap()].into_iter().collect();
+        let config = Config { unfailing: true, ..Config::default() };
+        let goal = Equation::parse("f(f(X, Y), Z) = f(Z, f(Y, X))").unwrap();
+        let outcome = kbc(state, std::slice::from_ref(&goal), &config);
+        assert!(matches!(outcome.status, Status::Converged));
+        assert_eq!(szs_status(&outcome, &[goal]), "GaveUp");
+        assert_eq!(szs_status(&outcome, &[Equation::parse("f(a, b) = b").unwrap()]), "CounterSatisfiable");
+    }
+}
@@ -3,30 +3,41 @@ use crate::*;
 // assumption: l and r have disjoint sets of vars.
 pub fn unify(l: &Term, r: &Term) -> Option<Subst> {
     let mut subst = Default::default();
-    unify_impl(l, r, &mut subst)?;
+    unify_impl(l, r, &mut subst, None)?;
+    Some(subst)
+}
+
+// like unify, but variables are only bound to terms of their sort.
+// vars contains the sorts of the variables of l and r, as far as they are known,
+// and is extended by the sorts learned from the unifier.
+pub fn unify_sorted(l: &Term, r: &Term, sig: &Signature, vars: &mut VarSorts) -> Option<Subst> {
+    let mut subst = Default::default();
+    unify_impl(l, r, &mut subst, Some((sig, vars)))?;
     Some(subst)
 }
 
 // invariants: subst is always fully simplified w.r.t. itself.
-fn unify_impl(l: &Term, r: &Term, subst: &mut Subst) -> Option<()> {
+fn unify_impl(l: &Term, r: &Term, subst: &mut Subst, mut sorts: Option<(&Signature, &mut VarSorts)>) -> Option<()> {
     if l == r { return Some(()) }
 
     // replace defined vars.
     if let Term::Var(lv) = l && let Some(lt) = subst.get(lv) {
-        return unify_impl(&lt.clone(), r, subst);
+        return unify_impl(&lt.clone(), r, subst, sorts);
     }
     if let Term::Var(rv) = r && let Some(rt) = subst.get(rv) {
-        return unify_impl(l, &rt.clone(), subst);
+        return unify_impl(l, &rt.clone(), subst, sorts);
     }
 
     // define vars.
     if let Term::Var(lv) = l && subst.get(lv).is_none() {
+        bind_sort(*lv, r, &mut sorts)?;
         subst_add(*lv, r.clone(), subst)?;
-        return unify_impl(l, r, subst);
+        return unify_impl(l, r, subst, sorts);
     }
     if let Term::Var(rv) = r && subst.get(rv).is_none() {
+        bind_sort(*rv, l, &mut sorts)?;
         subst_add(*rv, l.clone(), subst)?;
-        return unify_impl(l, r, subst);
+        return unify_impl(l, r, subst, sorts);
     }
 
     let Term::Fun(lf, largs) = l else { unreachable!() };
@@ -35,7 +46,19 @@ fn unify_impl(l: &Term, r: &Term, subst: &mut Subst) -> Option<()> {
     if lf != rf { return None }
     if largs.len() != rargs.len() { return None }
     for (ll, rr) in largs.iter().zip(rargs.iter()) {
-        unify_impl(ll, rr, subst)?;
+        unify_impl(ll, rr, subst, sorts.as_mut().map(|(sig, vars)| (*sig, &mut **vars)))?;
+    }
+    Some(())
+}
+
+// checks that v can be bound to t; a variable of unknown sort takes the sort of the other.
+fn bind_sort(v: Symbol, t: &Term, sorts: &mut Option<(&Signature, &mut VarSorts)>) -> Option<()> {
+    let Some((sig, vars)) = sorts else { return Some(()) };
+    if !sig.fits(v, t, vars) { return None }
+    match (vars.get(&v).copied(), t) {
+        (Some(s), Term::Var(x)) => { vars.insert(*x, s); },
+        (None, _) => if let Some(s) = sig.sort_of(t, vars) { vars.insert(v, s); },
+        _ => {},
     }
     Some(())
 }
@@ -53,7 +76,7 @@ fn subst_add(v: Symbol, t: Term, subst: &mut Subst) -> Option<()> {
 
     let old_subst = subst.clone();
 
-    for (vv, tt) in subst.iter_mut() {
+    for tt in subst.values_mut() {
         *tt = fix_apply_subst(tt.clone(), &old_subst);
     }
 